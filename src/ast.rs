@@ -1,11 +1,41 @@
+use crate::token::*;
+use crate::object::*;
+use crate::span::Span;
 
+/// An expression with the source text it was parsed from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span
+}
 
+impl Expr {
+    pub fn new(kind: ExprKind, span: Span) -> Expr {
+        Expr {
+            kind,
+            span
+        }
+    }
+}
 
+/// A statement with the source text it was parsed from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Span
+}
+
+impl Stmt {
+    pub fn new(kind: StmtKind, span: Span) -> Stmt {
+        Stmt {
+            kind,
+            span
+        }
+    }
+}
 
-use crate::token::*;
-use crate::object::*;
 #[derive(Debug, Clone, PartialEq)]
-pub enum Expr {
+pub enum ExprKind {
     Binary {
         left: Box<Expr>,
         operator: Token,
@@ -17,36 +47,154 @@ pub enum Expr {
         operator: Token,
         right: Box<Expr>
     },
+    /// `left = right` or a compound assignment like `left += right`, `operator`
+    /// telling which
     Assign {
         left: Box<Expr>,
         operator: Token,
         right: Box<Expr>
+    },
+    /// `++target`, `target++`, `--target` or `target--`
+    Increment {
+        target: Box<Expr>,
+        operator: Token,
+        prefix: bool
+    },
+    Call {
+        callee: Box<Expr>,
+        paren: Token,
+        arguments: Vec<Expr>
+    },
+    /// `[a, b, c]` or `[key => value, ...]`; elements without `key =>` have None keys
+    Array(Vec<(Option<Expr>, Expr)>),
+    /// `object[index]`; the index is None for the append form `object[]`
+    Index {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Option<Box<Expr>>
+    },
+    /// property access `object->name`
+    Get {
+        object: Box<Expr>,
+        name: Token
+    },
+    New {
+        class: Token,
+        arguments: Vec<Expr>
+    },
+    /// `parent::method`, the superclass' method bound to the current `this`
+    Parent {
+        keyword: Token,
+        method: Token
+    },
+    /// `match (subject) { a, b => x, c => y, default => z }`
+    Match {
+        keyword: Token,
+        subject: Box<Expr>,
+        arms: Vec<(Vec<Expr>, Expr)>,
+        default: Option<Box<Expr>>
+    },
+    /// `fn (params) { body }` or `fn (params) => expr`; an arrow
+    /// function's body is a single `return` of its expression.
+    Closure {
+        params: Vec<(Token, Option<Expr>)>,
+        body: Vec<Stmt>,
+        arrow: bool
     }
 
 }
-#[derive(Debug, Clone)]
-pub enum Stmt {
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum StmtKind {
     Print(Box<Stmt>),
     Expression(Box<Expr>),
+    /// `if c1 {} elseif c2 {} else if c3 {} else {}`: one branch per condition,
+    /// in source order, tried until one of them holds
     Ifstmt {
-        expr: Box<Expr>,
-        block: Box<Stmt>,
+        branches: Vec<(Expr, Stmt)>,
         elseblock: Option<Box<Stmt>>
     },
     WhileStmt {
         expr: Box<Expr>,
         block: Box<Stmt>
     },
+    /// `for (initializer; condition; increment) block`, every clause optional
+    ForStmt {
+        initializer: Option<Box<Stmt>>,
+        condition: Option<Box<Expr>>,
+        increment: Option<Box<Expr>>,
+        block: Box<Stmt>
+    },
+    /// `foreach (iterable as key => value) block`, key being optional
+    Foreach {
+        keyword: Token,
+        iterable: Box<Expr>,
+        key: Option<Token>,
+        value: Token,
+        block: Box<Stmt>
+    },
+    /// `switch (subject) { case value: ... default: ... }`; the default case has no value
+    Switch {
+        subject: Box<Expr>,
+        cases: Vec<(Option<Expr>, Vec<Stmt>)>
+    },
     Block(Vec<Stmt>),
     VariableAssign {
         identifier: String,
         value: Box<Expr>
     },
-    VariableCall {
-        identifier: Box<Expr>
+    /// `fn name(a, b = default) { body }`; each parameter comes with its default value, if any
+    Function {
+        name: Token,
+        params: Vec<(Token, Option<Expr>)>,
+        body: Vec<Stmt>
+    },
+    Return(Box<Expr>),
+    /// `break;` or `break depth;`, leaving `depth` enclosing loops
+    Break(usize),
+    /// `continue;` or `continue depth;`
+    Continue(usize),
+    /// `throw value;`
+    Throw {
+        keyword: Token,
+        value: Box<Expr>
+    },
+    /// `try { } catch (Type e) { } finally { }`; each catch clause has the class or
+    /// interface it handles, the variable the exception is bound to, if any, and its block
+    Try {
+        block: Vec<Stmt>,
+        catches: Vec<(Token, Option<Token>, Vec<Stmt>)>,
+        finally: Option<Vec<Stmt>>
+    },
+    /// `unset(a, b["key"], c->prop);`
+    Unset {
+        keyword: Token,
+        targets: Vec<Expr>
+    },
+    /// `abstract class Name extends Base implements I { use T; var prop = default; fn method() {} }`;
+    /// every entry of `methods` is a `Stmt::Function`
+    Class {
+        name: Token,
+        is_abstract: bool,
+        superclass: Option<Token>,
+        interfaces: Vec<Token>,
+        traits: Vec<Token>,
+        properties: Vec<(Token, Expr)>,
+        methods: Vec<Stmt>,
+        /// methods declared `abstract fn name();`
+        abstract_methods: Vec<Token>
+    },
+    /// `interface Name extends Other { fn method(); }`
+    Interface {
+        name: Token,
+        parents: Vec<Token>,
+        methods: Vec<Token>
+    },
+    /// `trait Name { ... }`, whose members are copied into every class that uses it
+    Trait {
+        name: Token,
+        properties: Vec<(Token, Expr)>,
+        methods: Vec<Stmt>,
+        abstract_methods: Vec<Token>
     }
 }
-
-
-
-
@@ -0,0 +1,173 @@
+use std::cmp::Ordering;
+use crate::decimal::Decimal;
+use crate::interpreter::Interpreter;
+use crate::object::{format_float, obj, Builtin, BuiltinError};
+
+/// Every builtin function; the interpreter defines them in the global scope.
+pub fn builtins() -> Vec<Builtin> {
+    vec![
+        Builtin { name: "count", arity: 1..=1, function: count },
+        Builtin { name: "intdiv", arity: 2..=2, function: intdiv },
+        Builtin { name: "fmod", arity: 2..=2, function: fmod },
+        Builtin { name: "bcadd", arity: 2..=3, function: bcadd },
+        Builtin { name: "bcsub", arity: 2..=3, function: bcsub },
+        Builtin { name: "bcmul", arity: 2..=3, function: bcmul },
+        Builtin { name: "bcdiv", arity: 2..=3, function: bcdiv },
+        Builtin { name: "bcmod", arity: 2..=3, function: bcmod },
+        Builtin { name: "bcpow", arity: 2..=3, function: bcpow },
+        Builtin { name: "bccomp", arity: 2..=3, function: bccomp },
+        Builtin { name: "bcscale", arity: 0..=1, function: bcscale },
+    ]
+}
+
+fn count(_interpreter: &mut Interpreter, arguments: Vec<obj>) -> Result<obj, BuiltinError> {
+    match &arguments[0] {
+        obj::Array(items) => Ok(obj::Int(items.len() as i64)),
+        obj::Map(map) => Ok(obj::Int(map.len() as i64)),
+        other => Err(format!("count(): argument must be an array, {} given", other.type_name()).into())
+    }
+}
+
+/// The number argument at `position`, or a TypeError naming `function`.
+fn number(function: &str, arguments: &[obj], position: usize) -> Result<obj, BuiltinError> {
+    match &arguments[position] {
+        number @ (obj::Int(_) | obj::Float(_)) => Ok(number.clone()),
+        other => Err(BuiltinError::Throw(
+            "TypeError",
+            format!("{function}(): argument #{} must be a number, {} given", position + 1, other.type_name())
+        ))
+    }
+}
+
+/// The integer quotient of the arguments, rounded towards zero.
+fn intdiv(_interpreter: &mut Interpreter, arguments: Vec<obj>) -> Result<obj, BuiltinError> {
+    let dividend = number("intdiv", &arguments, 0)?.to_int();
+    let divisor = number("intdiv", &arguments, 1)?.to_int();
+    if divisor == 0 {
+        return Err(BuiltinError::Throw("DivisionByZeroError", "Division by zero".to_string()));
+    }
+    match dividend.checked_div(divisor) {
+        Some(quotient) => Ok(obj::Int(quotient)),
+        None => Err(BuiltinError::Throw(
+            "ArithmeticError",
+            "Division of the smallest integer by -1 is not an integer".to_string()
+        ))
+    }
+}
+
+/// The floating point remainder of dividing the arguments, NAN for a zero divisor.
+fn fmod(_interpreter: &mut Interpreter, arguments: Vec<obj>) -> Result<obj, BuiltinError> {
+    let dividend = number("fmod", &arguments, 0)?.to_float();
+    let divisor = number("fmod", &arguments, 1)?.to_float();
+    Ok(obj::Float(dividend % divisor))
+}
+
+/// The arbitrary-precision number argument at `position`: a numeric string
+/// like "-12.50", or a number.
+fn decimal(function: &str, arguments: &[obj], position: usize) -> Result<Decimal, BuiltinError> {
+    let parsed = match &arguments[position] {
+        obj::Str(s) => Decimal::parse(s),
+        obj::Int(n) => Some(Decimal::from_int(*n)),
+        obj::Float(n) => Decimal::parse(&format_float(*n)),
+        other => {
+            return Err(BuiltinError::Throw(
+                "TypeError",
+                format!("{function}(): argument #{} must be a string, {} given", position + 1, other.type_name())
+            ));
+        }
+    };
+    parsed.ok_or_else(|| BuiltinError::Throw(
+        "ValueError",
+        format!("{function}(): argument #{} is not well-formed", position + 1)
+    ))
+}
+
+/// The scale argument at `position`, the number of digits kept after the
+/// decimal point; the default set with bcscale() when it is missing or null.
+fn scale(interpreter: &Interpreter, function: &str, arguments: &[obj], position: usize) -> Result<usize, BuiltinError> {
+    match arguments.get(position) {
+        None | Some(obj::Null) => Ok(interpreter.bc_scale),
+        Some(obj::Int(n)) => usize::try_from(*n).map_err(|_| BuiltinError::Throw(
+            "ValueError",
+            format!("{function}(): argument #{} must be greater than or equal to 0", position + 1)
+        )),
+        Some(other) => Err(BuiltinError::Throw(
+            "TypeError",
+            format!("{function}(): argument #{} must be an int, {} given", position + 1, other.type_name())
+        ))
+    }
+}
+
+/// The two numbers and the scale the two-operand bcmath functions take.
+fn operands(interpreter: &Interpreter, function: &str, arguments: &[obj]) -> Result<(Decimal, Decimal, usize), BuiltinError> {
+    Ok((
+        decimal(function, arguments, 0)?,
+        decimal(function, arguments, 1)?,
+        scale(interpreter, function, arguments, 2)?
+    ))
+}
+
+fn bcadd(interpreter: &mut Interpreter, arguments: Vec<obj>) -> Result<obj, BuiltinError> {
+    let (a, b, scale) = operands(interpreter, "bcadd", &arguments)?;
+    Ok(obj::Str(a.add(&b, scale).to_string()))
+}
+
+fn bcsub(interpreter: &mut Interpreter, arguments: Vec<obj>) -> Result<obj, BuiltinError> {
+    let (a, b, scale) = operands(interpreter, "bcsub", &arguments)?;
+    Ok(obj::Str(a.sub(&b, scale).to_string()))
+}
+
+fn bcmul(interpreter: &mut Interpreter, arguments: Vec<obj>) -> Result<obj, BuiltinError> {
+    let (a, b, scale) = operands(interpreter, "bcmul", &arguments)?;
+    Ok(obj::Str(a.mul(&b, scale).to_string()))
+}
+
+fn bcdiv(interpreter: &mut Interpreter, arguments: Vec<obj>) -> Result<obj, BuiltinError> {
+    let (a, b, scale) = operands(interpreter, "bcdiv", &arguments)?;
+    match a.div(&b, scale) {
+        Some(quotient) => Ok(obj::Str(quotient.to_string())),
+        None => Err(BuiltinError::Throw("DivisionByZeroError", "Division by zero".to_string()))
+    }
+}
+
+fn bcmod(interpreter: &mut Interpreter, arguments: Vec<obj>) -> Result<obj, BuiltinError> {
+    let (a, b, scale) = operands(interpreter, "bcmod", &arguments)?;
+    match a.rem(&b, scale) {
+        Some(remainder) => Ok(obj::Str(remainder.to_string())),
+        None => Err(BuiltinError::Throw("DivisionByZeroError", "Modulo by zero".to_string()))
+    }
+}
+
+fn bcpow(interpreter: &mut Interpreter, arguments: Vec<obj>) -> Result<obj, BuiltinError> {
+    let (base, exponent, scale) = operands(interpreter, "bcpow", &arguments)?;
+    let Some(exponent) = exponent.to_int() else {
+        return Err(BuiltinError::Throw(
+            "ValueError",
+            "bcpow(): argument #2 must be an integer that fits in 64 bits".to_string()
+        ));
+    };
+    match base.pow(exponent, scale) {
+        Some(power) => Ok(obj::Str(power.to_string())),
+        None => Err(BuiltinError::Throw("DivisionByZeroError", "Negative power of zero".to_string()))
+    }
+}
+
+/// -1, 0 or 1 as the first number is less than, equal to or greater than the
+/// second, comparing only `scale` digits after the decimal point.
+fn bccomp(interpreter: &mut Interpreter, arguments: Vec<obj>) -> Result<obj, BuiltinError> {
+    let (a, b, scale) = operands(interpreter, "bccomp", &arguments)?;
+    Ok(obj::Int(match a.rescale(scale).cmp(&b.rescale(scale)) {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1
+    }))
+}
+
+/// Sets the default scale of the bcmath functions when given one, and returns the previous scale.
+fn bcscale(interpreter: &mut Interpreter, arguments: Vec<obj>) -> Result<obj, BuiltinError> {
+    let previous = interpreter.bc_scale;
+    if !arguments.is_empty() {
+        interpreter.bc_scale = scale(interpreter, "bcscale", &arguments, 0)?;
+    }
+    Ok(obj::Int(previous as i64))
+}
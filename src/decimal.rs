@@ -0,0 +1,320 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+/// An arbitrary-precision decimal number, the value behind the bcmath builtins.
+/// It is `magnitude / 10^scale`, negated when `negative` is set.
+#[derive(Debug, Clone)]
+pub struct Decimal {
+    negative: bool,
+    /// the digits of the magnitude, least significant first, without leading zeros
+    magnitude: Vec<u8>,
+    /// how many of the digits are after the decimal point
+    scale: usize,
+}
+
+impl Decimal {
+    /// Parses a number in bcmath's format: an optional sign, digits and an
+    /// optional fraction, like "-12.50", ".5" or "7.". Anything else is None.
+    pub fn parse(s: &str) -> Option<Decimal> {
+        let (negative, unsigned) = match s.as_bytes().first()? {
+            b'-' => (true, &s[1..]),
+            b'+' => (false, &s[1..]),
+            _ => (false, s)
+        };
+        let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if integer.is_empty() && fraction.is_empty() || !all_digits(integer) || !all_digits(fraction) {
+            return None;
+        }
+        let magnitude = integer.bytes().chain(fraction.bytes()).rev().map(|b| b - b'0').collect();
+        Some(Decimal::new(negative, magnitude, fraction.len()))
+    }
+
+    pub fn from_int(n: i64) -> Decimal {
+        Decimal::parse(&n.to_string()).unwrap()
+    }
+
+    fn new(negative: bool, mut magnitude: Vec<u8>, scale: usize) -> Decimal {
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+        Decimal {
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+            scale
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    /// The number with exactly `scale` digits after the point, truncating the extra ones.
+    pub fn rescale(&self, scale: usize) -> Decimal {
+        let magnitude = if scale >= self.scale {
+            let mut magnitude = vec![0; scale - self.scale];
+            magnitude.extend(&self.magnitude);
+            magnitude
+        } else {
+            self.magnitude.iter().skip(self.scale - scale).copied().collect()
+        };
+        Decimal::new(self.negative, magnitude, scale)
+    }
+
+    pub fn add(&self, other: &Decimal, scale: usize) -> Decimal {
+        let common = self.scale.max(other.scale);
+        let (a, b) = (self.rescale(common), other.rescale(common));
+        let sum = if a.negative == b.negative {
+            Decimal::new(a.negative, add_magnitudes(&a.magnitude, &b.magnitude), common)
+        } else {
+            match compare_magnitudes(&a.magnitude, &b.magnitude) {
+                Ordering::Less => Decimal::new(b.negative, subtract_magnitudes(&b.magnitude, &a.magnitude), common),
+                _ => Decimal::new(a.negative, subtract_magnitudes(&a.magnitude, &b.magnitude), common)
+            }
+        };
+        sum.rescale(scale)
+    }
+
+    pub fn sub(&self, other: &Decimal, scale: usize) -> Decimal {
+        self.add(&other.negated(), scale)
+    }
+
+    pub fn mul(&self, other: &Decimal, scale: usize) -> Decimal {
+        let product = multiply_magnitudes(&self.magnitude, &other.magnitude);
+        Decimal::new(self.negative != other.negative, product, self.scale + other.scale).rescale(scale)
+    }
+
+    /// The quotient truncated to `scale` digits, or None when `other` is zero.
+    pub fn div(&self, other: &Decimal, scale: usize) -> Option<Decimal> {
+        if other.is_zero() {
+            return None;
+        }
+        // a / b = (A * 10^(b.scale + scale)) / (B * 10^a.scale), with scale digits after the point
+        let numerator = shift(&self.magnitude, other.scale + scale);
+        let denominator = shift(&other.magnitude, self.scale);
+        let (quotient, _) = divide_magnitudes(&numerator, &denominator);
+        Some(Decimal::new(self.negative != other.negative, quotient, scale))
+    }
+
+    /// `self - other * trunc(self / other)`, which has the sign of `self`,
+    /// or None when `other` is zero.
+    pub fn rem(&self, other: &Decimal, scale: usize) -> Option<Decimal> {
+        let quotient = self.div(other, 0)?;
+        let common = self.scale.max(other.scale + quotient.scale).max(scale);
+        Some(self.sub(&other.mul(&quotient, common), common).rescale(scale))
+    }
+
+    /// `self` to the power `exponent`; a negative power is worked out by division.
+    /// None when the base is zero and the power negative.
+    pub fn pow(&self, exponent: i64, scale: usize) -> Option<Decimal> {
+        let mut result = Decimal::from_int(1);
+        let mut base = self.clone();
+        let mut remaining = exponent.unsigned_abs();
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                result = result.mul(&base, result.scale + base.scale);
+            }
+            remaining >>= 1;
+            if remaining > 0 {
+                base = base.mul(&base, base.scale * 2);
+            }
+        }
+        if exponent < 0 {
+            Decimal::from_int(1).div(&result, scale)
+        } else {
+            Some(result.rescale(scale))
+        }
+    }
+
+    /// The integer value, for a number without a fraction that fits in 64 bits.
+    pub fn to_int(&self) -> Option<i64> {
+        if self.magnitude.iter().take(self.scale).any(|digit| *digit != 0) {
+            return None;
+        }
+        self.rescale(0).to_string().parse().ok()
+    }
+
+    fn negated(&self) -> Decimal {
+        Decimal::new(!self.negative, self.magnitude.clone(), self.scale)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Decimal) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Decimal) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Decimal) -> Ordering {
+        let common = self.scale.max(other.scale);
+        let (a, b) = (self.rescale(common), other.rescale(common));
+        match (a.negative, b.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitudes(&a.magnitude, &b.magnitude),
+            (true, true) => compare_magnitudes(&b.magnitude, &a.magnitude)
+        }
+    }
+}
+
+/// Prints the number with all of its `scale` digits after the point, like bcmath.
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut digits: String = self.magnitude.iter().rev().map(|digit| char::from(b'0' + digit)).collect();
+        if digits.len() <= self.scale {
+            digits.insert_str(0, &"0".repeat(self.scale + 1 - digits.len()));
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let (integer, fraction) = digits.split_at(digits.len() - self.scale);
+        if fraction.is_empty() {
+            write!(f, "{integer}")
+        } else {
+            write!(f, "{integer}.{fraction}")
+        }
+    }
+}
+
+fn compare_magnitudes(a: &[u8], b: &[u8]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitudes(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut sum = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let digit = a.get(i).unwrap_or(&0) + b.get(i).unwrap_or(&0) + carry;
+        sum.push(digit % 10);
+        carry = digit / 10;
+    }
+    if carry > 0 {
+        sum.push(carry);
+    }
+    sum
+}
+
+/// `a - b`, where `a` is at least `b`.
+fn subtract_magnitudes(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut difference = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for (i, digit) in a.iter().enumerate() {
+        let subtrahend = b.get(i).unwrap_or(&0) + borrow;
+        if *digit >= subtrahend {
+            difference.push(digit - subtrahend);
+            borrow = 0;
+        } else {
+            difference.push(digit + 10 - subtrahend);
+            borrow = 1;
+        }
+    }
+    trim(difference)
+}
+
+fn multiply_magnitudes(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut product = vec![0u32; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            product[i + j] += u32::from(*x) * u32::from(*y);
+        }
+        // carry as we go so the sums stay small
+        for k in i..product.len() - 1 {
+            product[k + 1] += product[k] / 10;
+            product[k] %= 10;
+        }
+    }
+    trim(product.into_iter().map(|digit| digit as u8).collect())
+}
+
+/// Long division of magnitudes, giving the quotient and the remainder.
+fn divide_magnitudes(numerator: &[u8], denominator: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut quotient = vec![0; numerator.len()];
+    let mut remainder: Vec<u8> = Vec::new();
+    for (i, digit) in numerator.iter().enumerate().rev() {
+        remainder.insert(0, *digit);
+        remainder = trim(remainder);
+        while compare_magnitudes(&remainder, denominator) != Ordering::Less {
+            remainder = subtract_magnitudes(&remainder, denominator);
+            quotient[i] += 1;
+        }
+    }
+    (trim(quotient), remainder)
+}
+
+/// The magnitude multiplied by `10^places`.
+fn shift(magnitude: &[u8], places: usize) -> Vec<u8> {
+    let mut shifted = vec![0; places];
+    shifted.extend(magnitude);
+    trim(shifted)
+}
+
+fn trim(mut magnitude: Vec<u8>) -> Vec<u8> {
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+    magnitude
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Decimal;
+
+    fn decimal(s: &str) -> Decimal {
+        Decimal::parse(s).unwrap()
+    }
+
+    #[test]
+    fn div_truncates_negative_quotients_towards_zero() {
+        assert_eq!(decimal("-7").div(&decimal("2"), 0).unwrap().to_string(), "-3");
+        assert_eq!(decimal("-1").div(&decimal("3"), 2).unwrap().to_string(), "-0.33");
+        assert_eq!(decimal("7").div(&decimal("-2"), 1).unwrap().to_string(), "-3.5");
+        assert!(decimal("1").div(&decimal("0.00"), 2).is_none());
+    }
+
+    #[test]
+    fn div_drops_the_sign_of_a_quotient_truncated_to_zero() {
+        assert_eq!(decimal("-1").div(&decimal("3"), 0).unwrap().to_string(), "0");
+    }
+
+    #[test]
+    fn rem_takes_the_sign_of_the_dividend() {
+        assert_eq!(decimal("-7").rem(&decimal("3"), 0).unwrap().to_string(), "-1");
+        assert_eq!(decimal("7").rem(&decimal("-3"), 0).unwrap().to_string(), "1");
+        assert_eq!(decimal("-7.5").rem(&decimal("2"), 1).unwrap().to_string(), "-1.5");
+        assert!(decimal("7").rem(&decimal("0"), 0).is_none());
+    }
+
+    #[test]
+    fn pow_with_a_negative_exponent_divides() {
+        assert_eq!(decimal("2").pow(-2, 2).unwrap().to_string(), "0.25");
+        assert_eq!(decimal("-3").pow(-1, 3).unwrap().to_string(), "-0.333");
+        assert_eq!(decimal("1.5").pow(3, 3).unwrap().to_string(), "3.375");
+        assert!(decimal("0").pow(-1, 2).is_none());
+    }
+
+    #[test]
+    fn fractional_exponents_have_no_integer_value() {
+        // bcpow rejects an exponent that `to_int` can't turn into an integer
+        assert_eq!(decimal("2.5").to_int(), None);
+        assert_eq!(decimal("3.000").to_int(), Some(3));
+        assert_eq!(decimal("-4").to_int(), Some(-4));
+        assert_eq!(decimal("9223372036854775808").to_int(), None);
+    }
+
+    #[test]
+    fn rescale_drops_the_sign_of_zero() {
+        assert_eq!(decimal("-0.001").rescale(2).to_string(), "0.00");
+        assert_eq!(decimal("-0.00").to_string(), "0.00");
+        assert_eq!(decimal("-1.239").rescale(2).to_string(), "-1.23");
+        assert_eq!(decimal("-1").rescale(2).to_string(), "-1.00");
+    }
+}
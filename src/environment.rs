@@ -0,0 +1,95 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::object::obj;
+
+/// One scope of variables. Scopes are chained through `enclosing`, from a
+/// block out to its function and finally to the global scope.
+#[derive(Debug, Default, Clone)]
+pub struct Environment {
+    values: HashMap<String, obj>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Environment {
+        Environment::default()
+    }
+
+    pub fn with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Environment {
+        Environment {
+            values: HashMap::new(),
+            enclosing: Some(enclosing),
+        }
+    }
+
+    /// Creates a binding in this scope, shadowing any outer binding of the same name.
+    pub fn define(&mut self, name: String, value: obj) {
+        self.values.insert(name, value);
+    }
+
+    /// Looks `name` up in this scope and then in each enclosing scope.
+    pub fn get(&self, name: &str) -> Option<obj> {
+        match self.values.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.enclosing.as_ref()?.borrow().get(name),
+        }
+    }
+
+    /// A scope with no enclosing scope, holding a copy of every binding
+    /// visible from this one.
+    pub fn snapshot(&self) -> Environment {
+        let mut values = match &self.enclosing {
+            Some(enclosing) => enclosing.borrow().snapshot().values,
+            None => HashMap::new(),
+        };
+        values.extend(self.values.clone());
+        Environment {
+            values,
+            enclosing: None,
+        }
+    }
+
+    /// The names bound in this scope and the enclosing ones.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.values.keys().cloned().collect();
+        if let Some(enclosing) = &self.enclosing {
+            names.extend(enclosing.borrow().names());
+        }
+        names
+    }
+
+    /// Runs `update` on the nearest existing binding of `name`, letting it
+    /// change the value in place. Returns None when `name` is not defined.
+    pub fn update<R>(&mut self, name: &str, update: impl FnOnce(&mut obj) -> R) -> Option<R> {
+        if let Some(slot) = self.values.get_mut(name) {
+            return Some(update(slot));
+        }
+        self.enclosing.as_ref()?.borrow_mut().update(name, update)
+    }
+
+    /// Deletes the nearest binding of `name`, returning false when there is none.
+    pub fn remove(&mut self, name: &str) -> bool {
+        if self.values.remove(name).is_some() {
+            return true;
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().remove(name),
+            None => false,
+        }
+    }
+
+    /// Overwrites the nearest existing binding of `name`.
+    /// Returns false when no scope in the chain defines it.
+    pub fn assign(&mut self, name: &str, value: obj) -> bool {
+        if let Some(slot) = self.values.get_mut(name) {
+            *slot = value;
+            return true;
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign(name, value),
+            None => false,
+        }
+    }
+}
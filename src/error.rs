@@ -1,27 +1,196 @@
 use colored::*;
-#[derive(Debug)]
-pub enum ScrapError {
+use crate::object::obj;
+use crate::span::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorType {
     ScannerError,
     ParserError,
     RuntimeError,
     EvaluatorError,
     InvalidSyntax,
+    /// a thrown exception; it unwinds as an error until a `catch` handles it
+    Exception,
+}
+
+/// An error raised while scanning, parsing or running a script.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScrapError {
+    pub et: ErrorType,
+    pub msg: String,
+    /// where in the script the error is
+    pub span: Span,
+    /// the Throwable instance, for an `Exception`
+    pub thrown: Option<Box<obj>>,
+    /// a hint shown under the source snippet
+    pub help: Option<String>,
 }
 
 impl ScrapError {
-    pub fn error(et: ScrapError, msg: &str, line: usize, filename: &str) {
+    #[must_use]
+    pub fn error(et: ErrorType, msg: &str, span: Span) -> ScrapError {
+        ScrapError {
+            et,
+            msg: msg.to_string(),
+            span,
+            thrown: None,
+            help: None
+        }
+    }
+
+    /// Adds a help note to the error.
+    #[must_use]
+    pub fn with_help(mut self, help: String) -> ScrapError {
+        self.help = Some(help);
+        self
+    }
+
+    /// The error for throwing `exception`, a Throwable instance, at `span`. Its
+    /// message carries the stack trace recorded when the exception was created.
+    pub fn exception(exception: obj, span: Span) -> ScrapError {
+        let mut msg = String::new();
+        if let obj::Instance(instance) = &exception {
+            let instance = instance.borrow();
+            let field = |name: &str| instance.fields.get(name).cloned().unwrap_or(obj::Null);
+            msg = format!("Uncaught {}: {}\nStack trace:", instance.class.name, field("message"));
+            if let obj::Array(frames) = field("trace") {
+                for frame in frames {
+                    msg.push_str(&format!("\n{frame}"));
+                }
+            }
+        }
+        ScrapError {
+            et: ErrorType::Exception,
+            msg,
+            span,
+            thrown: Some(Box::new(exception)),
+            help: None
+        }
+    }
+
+    /// The code shown with the error, one for each kind of error.
+    pub fn code(&self) -> &'static str {
+        match self.et {
+            ErrorType::ScannerError => "E0001",
+            ErrorType::ParserError => "E0002",
+            ErrorType::InvalidSyntax => "E0003",
+            ErrorType::RuntimeError => "E0004",
+            ErrorType::EvaluatorError => "E0005",
+            ErrorType::Exception => "E0006"
+        }
+    }
+
+    /// Prints the error the way rustc does: the message, the location, the
+    /// source line with the span underlined and the help note, if any.
+    /// `filename` and `source` are the name and text of the file the span is in.
+    pub fn report(&self, filename: &str, source: &str) {
+        let mut lines = self.msg.lines();
+        let headline = lines.next().unwrap_or_default();
+        eprintln!("{}{}", format!("error[{}]", self.code()).red().bold(), format!(": {headline}").bold());
+        if self.span.line == 0 {
+            return;
+        }
+        let number = self.span.line.to_string();
+        let gutter = " ".repeat(number.len());
+        eprintln!("{}{} {}:{}:{}", gutter, "-->".blue().bold(), filename, self.span.line, self.span.column);
+        let text = source.lines().nth(self.span.line - 1).unwrap_or_default();
+        // the underline ends with the line when the span continues on the next ones
+        let width = source.get(self.span.start..self.span.end)
+            .map(|spanned| spanned.chars().take_while(|c| *c != '\n').count())
+            .unwrap_or_default()
+            .max(1);
+        eprintln!("{} {}", gutter, "|".blue().bold());
+        eprintln!("{} {} {}", number.blue().bold(), "|".blue().bold(), text);
         eprintln!(
-            "{} {} {}",
-            format!("[{et:?}]").red(),
-            filename.to_string().blue().bold(),
-            format!("at line {line}: {msg}").red()
+            "{} {} {}{}",
+            gutter,
+            "|".blue().bold(),
+            " ".repeat(self.span.column.saturating_sub(1)),
+            "^".repeat(width).red().bold()
         );
-        match et {
-            Self::ScannerError => (),
-            Self::ParserError => std::process::exit(0),
-            Self::RuntimeError => std::process::exit(0),
-            Self::EvaluatorError => (),
-            Self::InvalidSyntax => std::process::exit(0)
+        if let Some(help) = &self.help {
+            eprintln!("{} {}", gutter, "|".blue().bold());
+            eprintln!("{} {} {}", gutter, "= help:".bold(), help);
+        }
+        for line in lines {
+            eprintln!("{line}");
+        }
+        eprintln!();
+    }
+
+    /// The error as one line of JSON, for tools that read diagnostics.
+    /// `filename` is the name of the file its span is in.
+    pub fn to_json(&self, filename: &str) -> String {
+        let help = match &self.help {
+            Some(help) => json_string(help),
+            None => "null".to_string()
+        };
+        format!(
+            "{{\"severity\":\"error\",\"code\":\"{}\",\"kind\":\"{:?}\",\"message\":{},\"help\":{},\"file\":{},\"line\":{},\"column\":{},\"span\":{{\"start\":{},\"end\":{}}}}}",
+            self.code(),
+            self.et,
+            json_string(&self.msg),
+            help,
+            json_string(filename),
+            self.span.line,
+            self.span.column,
+            self.span.start,
+            self.span.end
+        )
+    }
+
+    /// The status the process exits with after this error: 65 for errors in the
+    /// script's syntax, 70 for errors while running it and 255 for an uncaught exception.
+    pub fn exit_code(&self) -> i32 {
+        match self.et {
+            ErrorType::ScannerError | ErrorType::ParserError | ErrorType::InvalidSyntax => 65,
+            ErrorType::RuntimeError | ErrorType::EvaluatorError => 70,
+            ErrorType::Exception => 255
+        }
+    }
+}
+
+/// `text` as a JSON string literal.
+fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c)
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// The candidate closest to `name`, for a "did you mean" note. Only a
+/// candidate that is a few edits away from `name` is suggested.
+pub fn did_you_mean<'a>(name: &str, candidates: impl IntoIterator<Item = &'a String>) -> Option<&'a String> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// The Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
         }
+        previous = current;
     }
+    previous[b.len()]
 }
@@ -1,509 +1,1626 @@
-use std::collections::HashMap;
-use std::fmt::format;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 
-
-use crate::ast::{Expr, Stmt};
-use crate::error::ScrapError;
-use crate::error::ScrapError::{EvaluatorError, InvalidSyntax};
-use crate::object::obj;
+use crate::ast::{Expr, ExprKind, Stmt, StmtKind};
+use crate::builtins::builtins;
+use crate::environment::Environment;
+use crate::map::{Key, OrderedMap};
+use crate::error::{did_you_mean, ScrapError};
+use crate::error::ErrorType::{EvaluatorError, InvalidSyntax, RuntimeError};
+use crate::object::{increment_string, numeric_string, obj, Builtin, BuiltinError, Class, Function, Instance, Interface, Trait};
+use crate::prelude::prelude;
+use crate::span::Span;
+use crate::token::Token;
 use crate::tokentype::TType;
 
+/// How many calls may be nested before a script is stopped, so runaway recursion
+/// gives an error instead of overflowing the stack `main` runs the script on.
+pub const MAX_CALL_DEPTH: usize = 5_000;
+
+/// The stack `main` runs scripts on, with room for `MAX_CALL_DEPTH` nested calls even
+/// in a debug build, where every call takes a few dozen kilobytes.
+pub const STACK_SIZE: usize = MAX_CALL_DEPTH * 100 * 1024;
+
+/// How close to the end of the stack a script may get before it is stopped. Deeply
+/// nested expressions and statements use stack too, so calls alone can't be counted on.
+const STACK_MARGIN: usize = 1024 * 1024;
+
+/// What a statement asks its enclosing statements to do once it has run.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Flow {
+    Normal,
+    Return(obj),
+    /// leave this many enclosing loops
+    Break(usize),
+    /// go on with the next iteration of the loop this many levels out
+    Continue(usize)
+}
+
+impl Flow {
+    /// What a loop does once its body has finished with this flow: None to go on
+    /// with the next iteration, or the flow to hand to the loop's own enclosing statement.
+    fn exit_loop(self) -> Option<Flow> {
+        match self {
+            Flow::Normal | Flow::Continue(1) => None,
+            Flow::Break(1) => Some(Flow::Normal),
+            Flow::Break(depth) => Some(Flow::Break(depth - 1)),
+            Flow::Continue(depth) => Some(Flow::Continue(depth - 1)),
+            Flow::Return(value) => Some(Flow::Return(value))
+        }
+    }
+}
+
 pub struct Interpreter {
-    pub expressions: Vec<Expr>,
-    pub variables: HashMap<String, obj>,
+    /// the innermost scope of the code currently running
+    environment: Rc<RefCell<Environment>>,
     pub  statements: Vec<Stmt>,
     index: usize,
+    /// the functions being called, innermost last, with the line each was called from
+    call_stack: Vec<(String, usize)>,
+    /// the named functions and builtins, kept apart from the variables so
+    /// assigning to a variable can't replace one
+    functions: HashMap<String, obj>,
+    /// the declared classes, interfaces and traits
+    classes: HashMap<String, obj>,
+    /// the prelude's Throwable interface, which everything thrown must implement
+    throwable: Option<Rc<Interface>>,
+    /// the default scale of the bcmath functions, set with bcscale()
+    pub bc_scale: usize,
+    /// the address of the stack when the interpreter was created, to measure how much
+    /// the script has used since
+    stack_base: usize
 }
 
 impl Interpreter {
-    pub fn new(expressions: Vec<Expr>, statements: Vec<Stmt>) -> Interpreter {
-        Interpreter {
-            expressions,
+    pub fn new(statements: Vec<Stmt>) -> Interpreter {
+        let functions = builtins()
+            .into_iter()
+            .map(|builtin| (builtin.name.to_string(), obj::Builtin(builtin)))
+            .collect();
+        let mut interpreter = Interpreter {
             statements,
-            variables: HashMap::new(),
-            index: 0
+            environment: Rc::new(RefCell::new(Environment::new())),
+            index: 0,
+            call_stack: Vec::new(),
+            functions,
+            classes: HashMap::new(),
+            throwable: None,
+            bc_scale: 0,
+            stack_base: stack_address()
+        };
+        Stmt::run_block(&prelude(), &mut interpreter).expect("the prelude runs without errors");
+        if let Some(obj::Interface(throwable)) = interpreter.classes.get("Throwable") {
+            interpreter.throwable = Some(Rc::clone(throwable));
         }
+        interpreter
     }
 
-    pub fn start(&mut self) {
+    pub fn start(&mut self) -> Result<(), ScrapError> {
+        let hoisted = self.hoist();
         while self.index < self.statements.len() {
-            Stmt::run_stmt(self.statements[self.index].clone(),  self);
+            if hoisted.contains(&self.index) {
+                self.index += 1;
+                continue;
+            }
+            let stmt = self.statements[self.index].clone();
+            // a top-level `return` ends the script
+            if let Flow::Return(_) = Stmt::run_stmt(&stmt, self)? {
+                break;
+            }
             self.index += 1;
         }
+        Ok(())
     }
-}
-impl Expr {
-    fn evaluate(&self, mut interpreter: &mut Interpreter) -> obj {
-        match self {
-            Expr::Grouping(expr) => {
-               return expr.evaluate(interpreter)
-            },
-            Expr::Assign {left,operator,right} => {
-                let mut right = right.evaluate(interpreter);
-                let mut left = match *left.clone() {
-                    Expr::Literal(obj) => {
-                        match obj {
-                            obj::Identifier(id) => {
-                                id
-                            }
-                            _ => {
-                                ScrapError::error(
-                                    EvaluatorError,
-                                    "can't assign to this type",
-                                    line!() as usize,
-                                    file!()
-                                );
-                                String::new()
-                            }
-                        }
-                    }
-                    _ => {
-                        ScrapError::error(
-                            EvaluatorError,
-                            "can't assign",
-                            line!() as usize,
-                            file!()
-                        );
-                        String::new()
+
+    /// Declares the script's top-level functions, classes, interfaces and traits
+    /// before it runs, so they can be used above their declarations, and returns
+    /// the indices of the statements declared. A class whose parents or traits
+    /// aren't declared yet waits for them; one that can't be declared at all is
+    /// left to report its error when the script reaches it.
+    fn hoist(&mut self) -> HashSet<usize> {
+        let mut hoisted = HashSet::new();
+        let mut pending = Vec::new();
+        for (index, stmt) in self.statements.iter().enumerate() {
+            match &stmt.kind {
+                StmtKind::Function {name, params, body} => {
+                    let function = self.make_function(&name.literal, params, body);
+                    self.functions.insert(name.literal.clone(), obj::Function(function));
+                    hoisted.insert(index);
+                }
+                StmtKind::Class {..} | StmtKind::Interface {..} | StmtKind::Trait {..} => pending.push(index),
+                _ => {}
+            }
+        }
+        loop {
+            let before = pending.len();
+            pending.retain(|&index| {
+                let stmt = self.statements[index].clone();
+                match self.declare(&stmt) {
+                    Ok(()) => {
+                        hoisted.insert(index);
+                        false
                     }
+                    Err(_) => true
+                }
+            });
+            if pending.len() == before {
+                return hoisted;
+            }
+        }
+    }
+
+    /// The call stack as PHP prints it, innermost call first.
+    fn stack_trace(&self) -> Vec<obj> {
+        let mut frames: Vec<obj> = self.call_stack
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, (name, line))| obj::Str(format!("#{i} line {line}: {name}()")))
+            .collect();
+        frames.push(obj::Str(format!("#{} {{main}}", frames.len())));
+        frames
+    }
+
+    /// Creates an instance of the prelude class `class`, for errors a script can catch.
+    fn throw(&mut self, class: &str, message: String, span: Span) -> ScrapError {
+        match self.classes.get(class).cloned() {
+            Some(obj::Class(class)) => match self.instantiate(class, vec![obj::Str(message)], span) {
+                Ok(exception) => ScrapError::exception(exception, span),
+                Err(error) => error
+            },
+            _ => ScrapError::error(RuntimeError, format!("{class}: {message}").as_str(), span)
+        }
+    }
+
+    /// True when `exception` is an instance of the class or interface named by a catch clause.
+    fn is_caught_by(&self, exception: &obj, class: &Token) -> bool {
+        let obj::Instance(instance) = exception else {
+            return false;
+        };
+        match self.classes.get(&class.literal) {
+            Some(obj::Class(class)) => instance.borrow().class.is_subclass_of(class),
+            Some(obj::Interface(interface)) => instance.borrow().class.implements(interface),
+            _ => false
+        }
+    }
+
+    /// Stops a script that has nearly used up the stack it runs on, which it can
+    /// do before `MAX_CALL_DEPTH` is reached when it nests deeply between calls.
+    fn check_stack(&self, span: Span) -> Result<(), ScrapError> {
+        if self.stack_base.abs_diff(stack_address()) < STACK_SIZE - STACK_MARGIN {
+            return Ok(());
+        }
+        Err(ScrapError::error(RuntimeError, "the script nests too deeply for the stack", span)
+            .with_help("check for recursion that never stops".to_string()))
+    }
+
+    fn lookup(&self, name: &str) -> Option<obj> {
+        self.environment.borrow().get(name)
+    }
+
+    fn define(&mut self, name: String, value: obj) {
+        self.environment.borrow_mut().define(name, value);
+    }
 
+    /// Assigns to the nearest existing binding, or creates one in the
+    /// current scope when the variable has not been declared yet.
+    fn assign(&mut self, name: String, value: obj) {
+        if !self.environment.borrow_mut().assign(&name, value.clone()) {
+            self.define(name, value);
+        }
+    }
+
+    /// Runs `body` with `scope` as the current scope, restoring the previous one
+    /// afterwards, also when an exception is thrown.
+    fn with_scope<T>(&mut self, scope: Rc<RefCell<Environment>>, body: impl FnOnce(&mut Interpreter) -> T) -> T {
+        let previous = std::mem::replace(&mut self.environment, scope);
+        let result = body(self);
+        self.environment = previous;
+        result
+    }
+
+    /// Runs `stmts` inside `environment`, restoring the current scope afterwards.
+    fn execute_block(&mut self, stmts: &[Stmt], environment: Environment) -> Result<Flow, ScrapError> {
+        self.with_scope(Rc::new(RefCell::new(environment)), |interpreter| Stmt::run_block(stmts, interpreter))
+    }
+
+    fn make_function(&self, name: &str, params: &[(Token, Option<Expr>)], body: &[Stmt]) -> Rc<Function> {
+        Rc::new(Function {
+            name: name.to_string(),
+            params: params.to_vec(),
+            body: Rc::new(body.to_vec()),
+            closure: Rc::clone(&self.environment)
+        })
+    }
+
+    /// Creates a closure. An arrow function captures the variables it can see by
+    /// value, like PHP's `fn`, so later changes to them don't reach it.
+    fn make_closure(&self, params: &[(Token, Option<Expr>)], body: &[Stmt], arrow: bool) -> Rc<Function> {
+        let closure = if arrow {
+            Rc::new(RefCell::new(self.environment.borrow().snapshot()))
+        } else {
+            Rc::clone(&self.environment)
+        };
+        Rc::new(Function {
+            name: "{closure}".to_string(),
+            params: params.to_vec(),
+            body: Rc::new(body.to_vec()),
+            closure
+        })
+    }
+
+    /// Runs a function, class, interface or trait declaration.
+    #[inline(never)]
+    fn declare(&mut self, declaration: &Stmt) -> Result<(), ScrapError> {
+        match &declaration.kind {
+            StmtKind::Function {name, params, body} => {
+                let function = self.make_function(&name.literal, params, body);
+                self.functions.insert(name.literal.clone(), obj::Function(function));
+            }
+            StmtKind::Class {..} => self.declare_class(declaration)?,
+            StmtKind::Interface {name, parents, methods} => self.declare_interface(name, parents, methods)?,
+            StmtKind::Trait {name, properties, methods, abstract_methods} => {
+                let declared = Trait {
+                    name: name.literal.clone(),
+                    properties: properties.clone(),
+                    methods: methods.clone(),
+                    abstract_methods: abstract_methods.clone()
                 };
-                match operator.ttype {
-                    TType::Equal => {
-                        if interpreter.variables.contains_key(&*left) {
-                            interpreter.variables.remove(&*left);
-                            interpreter.variables.insert(left.clone(), right);
-                            return obj::Identifier(left.clone());
-                        } else {
-                            interpreter.variables.insert(left.clone(),right);
-                            return obj::Identifier(left.clone());
-                        }
-                    }
-                    _ => {
-                        ScrapError::error(
-                            EvaluatorError,
-                            "can't assign",
-                            line!() as usize,
-                            file!()
-                        );
-                        obj::Null
-                    }
+                self.classes.insert(name.literal.clone(), obj::Trait(Rc::new(declared)));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn declare_class(&mut self, declaration: &Stmt) -> Result<(), ScrapError> {
+        let StmtKind::Class {
+            name,
+            is_abstract,
+            superclass,
+            interfaces,
+            traits,
+            properties,
+            methods,
+            abstract_methods
+        } = &declaration.kind else {
+            return Ok(());
+        };
+
+        let superclass = match superclass {
+            Some(token) => match self.lookup_declared(token)? {
+                obj::Class(class) => Some(class),
+                _ => {
+                    return Err(ScrapError::error(
+                        RuntimeError,
+                        format!("'{}' is not a class and can't be extended", token.literal).as_str(),
+                        token.span
+                    ));
+                }
+            },
+            None => None
+        };
+        let mut class_interfaces = Vec::new();
+        for token in interfaces {
+            match self.lookup_declared(token)? {
+                obj::Interface(interface) => class_interfaces.push(interface),
+                _ => {
+                    return Err(ScrapError::error(
+                        RuntimeError,
+                        format!("'{}' is not an interface", token.literal).as_str(),
+                        token.span
+                    ));
                 }
+            }
+        }
+        let mut class_traits = Vec::new();
+        for token in traits {
+            match self.lookup_declared(token)? {
+                obj::Trait(used) => class_traits.push(used),
+                _ => {
+                    return Err(ScrapError::error(
+                        RuntimeError,
+                        format!("'{}' is not a trait", token.literal).as_str(),
+                        token.span
+                    ));
+                }
+            }
+        }
 
+        // members come from the superclass, then used traits, then the class itself,
+        // each overriding the one before
+        let mut class_properties = match &superclass {
+            Some(superclass) => superclass.properties.clone(),
+            None => Vec::new()
+        };
+        let trait_properties = class_traits.iter().flat_map(|used| used.properties.iter());
+        for (property, value) in trait_properties.chain(properties) {
+            let value = value.evaluate(self)?;
+            match class_properties.iter_mut().find(|(name, _)| name == &property.literal) {
+                Some(inherited) => inherited.1 = value,
+                None => class_properties.push((property.literal.clone(), value))
+            }
+        }
 
+        // methods of a subclass see their superclass as `parent`
+        let previous = superclass.as_ref().map(|superclass| {
+            let mut environment = Environment::with_enclosing(Rc::clone(&self.environment));
+            environment.define("parent".to_string(), obj::Class(Rc::clone(superclass)));
+            std::mem::replace(&mut self.environment, Rc::new(RefCell::new(environment)))
+        });
+        let mut class_methods = HashMap::new();
+        let trait_methods = class_traits.iter().flat_map(|used| used.methods.iter());
+        for method in trait_methods.chain(methods) {
+            if let StmtKind::Function {name, params, body} = &method.kind {
+                let function = self.make_function(&name.literal, params, body);
+                class_methods.insert(name.literal.clone(), function);
             }
-            Expr::Binary {left,operator,right} => {
-                let mut left = left.evaluate(interpreter);
-                let mut right = right.evaluate(interpreter);
-                match left {
-                    obj::Identifier(ref s) => {
-                        if interpreter.variables.get(s.as_str()).is_some() {
-                            left = interpreter
-                                .variables
-                                .get(s.as_str())
-                                .unwrap().clone();
-                        }
-                    }
-                    _ => {
-                        left = left;
-                    }
+        }
+        if let Some(previous) = previous {
+            self.environment = previous;
+        }
+
+        // every abstract or interface method, with where it was declared
+        let mut required: Vec<(String, String)> = Vec::new();
+        if let Some(superclass) = &superclass {
+            for method in &superclass.abstract_methods {
+                required.push((method.clone(), superclass.name.clone()));
+            }
+        }
+        for used in &class_traits {
+            for method in &used.abstract_methods {
+                required.push((method.literal.clone(), used.name.clone()));
+            }
+        }
+        for method in abstract_methods {
+            required.push((method.literal.clone(), name.literal.clone()));
+        }
+        for interface in &class_interfaces {
+            for method in interface.all_methods() {
+                required.push((method, interface.name.clone()));
+            }
+        }
+
+        let mut class = Class {
+            name: name.literal.clone(),
+            is_abstract: *is_abstract,
+            superclass,
+            interfaces: class_interfaces,
+            properties: class_properties,
+            methods: class_methods,
+            abstract_methods: Vec::new()
+        };
+        let mut missing: Vec<(String, String)> = Vec::new();
+        for (method, origin) in required {
+            let implemented = class.find_method(&method).is_some();
+            if !implemented && !missing.iter().any(|(name, _)| name == &method) {
+                missing.push((method, origin));
+            }
+        }
+        if !missing.is_empty() && !class.is_abstract {
+            let methods: Vec<String> = missing
+                .iter()
+                .map(|(method, origin)| format!("{origin}::{method}()"))
+                .collect();
+            return Err(ScrapError::error(
+                RuntimeError,
+                format!(
+                    "class {} must implement {} or be declared abstract",
+                    class.name,
+                    methods.join(", ")
+                ).as_str(),
+                name.span
+            ));
+        }
+        class.abstract_methods = missing.into_iter().map(|(method, _)| method).collect();
+        self.classes.insert(name.literal.clone(), obj::Class(Rc::new(class)));
+        Ok(())
+    }
+
+    fn declare_interface(&mut self, name: &Token, parents: &[Token], methods: &[Token]) -> Result<(), ScrapError> {
+        let mut interface_parents = Vec::new();
+        for token in parents {
+            match self.lookup_declared(token)? {
+                obj::Interface(parent) => interface_parents.push(parent),
+                _ => {
+                    return Err(ScrapError::error(
+                        RuntimeError,
+                        format!("'{}' is not an interface", token.literal).as_str(),
+                        token.span
+                    ));
                 }
-                // println!("{left}");
-                match right {
-                    obj::Identifier(ref s) => {
-                        if interpreter.variables.get(s.as_str()).is_some() {
-                            right = interpreter.variables.get(s.as_str()).unwrap().clone();
-                        }
-                    }
-                    _ => {
-                        right = right;
-                    }
+            }
+        }
+        let interface = Interface {
+            name: name.literal.clone(),
+            parents: interface_parents,
+            methods: methods.iter().map(|method| method.literal.clone()).collect()
+        };
+        self.classes.insert(name.literal.clone(), obj::Interface(Rc::new(interface)));
+        Ok(())
+    }
+
+    /// Looks up the class, interface or trait named by `token`.
+    fn lookup_declared(&self, token: &Token) -> Result<obj, ScrapError> {
+        match self.classes.get(&token.literal) {
+            Some(value) => Ok(value.clone()),
+            None => Err(self.undefined(
+                format!("'{}' not found", token.literal),
+                &token.literal,
+                self.classes.keys().cloned().collect(),
+                token.span
+            ))
+        }
+    }
+
+    /// The error for using `name` when nothing is defined under that name,
+    /// suggesting one of `names` that is spelled similarly.
+    fn undefined(&self, msg: String, name: &str, names: Vec<String>, span: Span) -> ScrapError {
+        let error = ScrapError::error(RuntimeError, msg.as_str(), span);
+        match did_you_mean(name, &names) {
+            Some(similar) => error.with_help(format!("did you mean `{similar}`?")),
+            None => error
+        }
+    }
+
+    /// Creates an instance of `class` and runs its `__construct` method, if any.
+    fn instantiate(&mut self, class: Rc<Class>, arguments: Vec<obj>, span: Span) -> Result<obj, ScrapError> {
+        if class.is_abstract {
+            return Err(ScrapError::error(
+                RuntimeError,
+                format!("cannot instantiate abstract class {}", class.name).as_str(),
+                span
+            ));
+        }
+        let mut instance = Instance::new(Rc::clone(&class));
+        // like PHP, a Throwable records where it was created
+        if self.throwable.as_ref().is_some_and(|throwable| class.implements(throwable)) {
+            instance.fields.insert("line".to_string(), obj::Int(span.line as i64));
+            instance.fields.insert("trace".to_string(), obj::Array(self.stack_trace()));
+        }
+        let instance = obj::Instance(Rc::new(RefCell::new(instance)));
+        match class.find_method("__construct") {
+            Some(constructor) => {
+                self.call(&constructor.bind(instance.clone()), arguments, span)?;
+            }
+            None if !arguments.is_empty() => {
+                return Err(ScrapError::error(
+                    RuntimeError,
+                    format!("class '{}' has no constructor but was given arguments", class.name).as_str(),
+                    span
+                ));
+            }
+            None => {}
+        }
+        Ok(instance)
+    }
+
+    /// Reads a property, or a method bound to the instance, from `object`.
+    fn get_property(&mut self, object: obj, name: &Token) -> Result<obj, ScrapError> {
+        let instance = match object {
+            obj::Instance(ref instance) => Rc::clone(instance),
+            _ => {
+                return Err(ScrapError::error(
+                    RuntimeError,
+                    format!("can't read property '{}' of a non-object", name.literal).as_str(),
+                    name.span
+                ));
+            }
+        };
+        let field = instance.borrow().fields.get(&name.literal).cloned();
+        if let Some(value) = field {
+            return Ok(value);
+        }
+        let method = instance.borrow().class.find_method(&name.literal);
+        match method {
+            Some(method) => Ok(obj::Function(Rc::new(method.bind(object)))),
+            None => Err(ScrapError::error(
+                RuntimeError,
+                format!(
+                    "undefined property {}->{}",
+                    instance.borrow().class.name,
+                    name.literal
+                ).as_str(),
+                name.span
+            ))
+        }
+    }
+
+    /// Works out which variable, property or array element `target` stands for,
+    /// evaluating the object and index expressions on the way exactly once.
+    fn resolve(&mut self, target: &Expr, span: Span) -> Result<Place, ScrapError> {
+        let mut indices = Vec::new();
+        let mut base = target;
+        while let ExprKind::Index {object, bracket, index} = &base.kind {
+            indices.push((index, bracket.span));
+            base = object;
+        }
+        let base = match &base.kind {
+            ExprKind::Literal(obj::Identifier(name)) => PlaceBase::Variable(name.clone(), base.span),
+            ExprKind::Get {object, name} => PlaceBase::Property(object.evaluate(self)?, name.clone()),
+            _ if indices.is_empty() => {
+                return Err(ScrapError::error(EvaluatorError, "can't assign to this type", span));
+            }
+            _ => {
+                return Err(ScrapError::error(
+                    RuntimeError,
+                    "can't assign to an element of a temporary value",
+                    span
+                ));
+            }
+        };
+        let mut place = Place {
+            base,
+            indices: Vec::new(),
+            brackets: Vec::new()
+        };
+        for (index, bracket) in indices.into_iter().rev() {
+            place.indices.push(index.as_ref().map(|index| index.evaluate(self)).transpose()?);
+            place.brackets.push(bracket);
+        }
+        Ok(place)
+    }
+
+    /// The value at `place`, with the errors reading its expression would give.
+    fn read_place(&mut self, place: &Place) -> Result<obj, ScrapError> {
+        let mut value = match &place.base {
+            PlaceBase::Variable(name, span) => match self.lookup(name) {
+                Some(value) => value,
+                None => return Err(self.undefined_variable(name, *span))
+            },
+            PlaceBase::Property(object, name) => self.get_property(object.clone(), name)?
+        };
+        for (index, bracket) in place.indices.iter().zip(&place.brackets) {
+            let element = match index {
+                Some(index) => read_element(&value, index),
+                None => Err("cannot use [] for reading".to_string())
+            };
+            value = element.map_err(|msg| ScrapError::error(RuntimeError, msg.as_str(), *bracket))?;
+        }
+        Ok(value)
+    }
+
+    /// Applies `write` at `place`. Array elements are changed inside the
+    /// variable or property holding the array.
+    fn write_place(&mut self, place: Place, write: Write, span: Span) -> Result<(), ScrapError> {
+        let result = match place.base {
+            PlaceBase::Variable(name, _) if place.indices.is_empty() => {
+                if let Write::Set(value) = write {
+                    self.assign(name, value);
                 }
-                // println!("{right}");
-                match (left.clone(), right.clone()) {
-                    (obj::Num(n1), obj::Num(n2)) => {
-                        match operator.ttype {
-                            TType::Plus => {
-                                obj::Num(n1 + n2)
-                            },
-                            TType::Minus => {
-                                obj::Num(n1 - n2)
-                            },
-                            TType::Slash => {
-                                obj::Num(n1 / n2)
-                            },
-                            TType::Star => {
-                                obj::Num(n1 * n2)
-                            },
-                            TType::GreaterEqual => {
-                                if n1 > n2 || n1 == n2 {
-                                    obj::Bool(true)
-                                } else {
-                                    obj::Bool(false)
-                                }
-                            },
-                            TType::LessEqual => {
-                                if n1 < n2 || n1 == n2 {
-                                    obj::Bool(true)
-                                } else {
-                                    obj::Bool(false)
-                                }
-                            },
-                            TType::EqualEqual => {
-                                if n1 == n2 {
-                                    obj::Bool(true)
-                                } else {
-                                    obj::Bool(false)
-                                }
-                            },
-                            TType::Greater => {
-                                if n1 > n2 {
-                                    obj::Bool(true)
-                                } else {
-                                    obj::Bool(false)
-                                }
-                            },
-                            TType::Less => {
-                                if n1 < n2 {
-                                    obj::Bool(true)
-                                } else {
-                                    obj::Bool(false)
-                                }
-                            },
-                            TType::BangEqual => {
-                                if n1 == n2 {
-                                    obj::Bool(false)
-                                } else {
-                                    obj::Bool(true)
-                                }
-                            }
-                            _ => {
-                                ScrapError::error(
-                                    EvaluatorError,
-                                    "undefined binary operator",
-                                    operator.line, file!()
-                                );
-                                obj::Null
-                            }
-                        }
-                    },
-                    (obj::Str(s1), obj::Str(s2)) => {
-                        match operator.ttype {
-                            TType::Plus => {
-                                let s1 = s1.replace('"', "");
-                                let s2 = s2.replace('"', "");
-                                let mut str = s1;
-                                str.push_str(&*s2);
-                                obj::Str(str)
-                            }
-                            TType::Minus => {
-                                let s1 = s1.replace('"', "");
-                                let s2 = s2.replace('"', "");
-                                let str = s1.replace(&s2, "");
-                                obj::Str(str)
-                            }
-                            _ => {
-                                ScrapError::error(
-                                    InvalidSyntax,
-                                    "unable to '-', '*' '/' a string ",
-                                    operator.line, file!()
-                                );
-                                obj::Null
-                            }
-                        }
-                    },
-                    (obj::Bool(b1), obj::Bool(b2)) => {
-                        match operator.ttype {
-                            TType::EqualEqual => {
-                                if b1 == b2 {
-                                    obj::Bool(true)
-                                } else {
-                                    obj::Bool(false)
-                                }
-                            },
-                            TType::BangEqual => {
-                                if b1 == b2 {
-                                    obj::Bool(false)
-                                } else {
-                                    obj::Bool(true)
-                                }
-                            },
-                            TType::And => {
-                                if b1 && b2 {
-                                    obj::Bool(true)
-                                } else {
-                                    obj::Bool(false)
-                                }
-                            },
-                            TType::Or => {
-                                if b1 || b2 {
-                                    obj::Bool(true)
-                                } else {
-                                    obj::Bool(false)
-                                }
-                            }
-                            _ => {
-                                ScrapError::error(
-                                    EvaluatorError,
-                                    "unable to do this operation on boolean values",
-                                    operator.line, file!()
-                                );
-                                obj::Null
-                            }
-                        }
-                    },
-                    (_, obj::Num(_n)) | (obj::Num(_n), _) => {
-                        ScrapError::error(
-                            InvalidSyntax,
-                            "unable to '+', '-', '*' and '/' here",
-                            operator.line, file!()
-                        );
-                        obj::Null
-                    }
-                    _ => {
-                        obj::Null
+                Ok(())
+            }
+            PlaceBase::Property(object, name) if place.indices.is_empty() => {
+                return match write {
+                    Write::Set(value) => self.set_property(object, &name, value),
+                    Write::Unset => Ok(())
+                };
+            }
+            PlaceBase::Variable(name, _) => {
+                if self.lookup(&name).is_none() {
+                    if let Write::Unset = write {
+                        return Ok(());
                     }
+                    self.define(name.clone(), obj::Null);
                 }
+                self.environment
+                    .borrow_mut()
+                    .update(&name, |slot| write_nested(slot, &place.indices, write))
+                    .unwrap_or(Ok(()))
+            }
+            PlaceBase::Property(obj::Instance(instance), name) => {
+                let mut instance = instance.borrow_mut();
+                let slot = instance.fields.entry(name.literal.clone()).or_insert(obj::Null);
+                write_nested(slot, &place.indices, write)
+            }
+            PlaceBase::Property(other, name) => {
+                Err(format!("can't set property '{}' on a {}", name.literal, other.type_name()))
+            }
+        };
+        if let Err(msg) = result {
+            return Err(ScrapError::error(RuntimeError, msg.as_str(), span));
+        }
+        Ok(())
+    }
+
+    /// Stores `value` in `target`, a variable, property or array element.
+    fn store(&mut self, target: &Expr, value: obj, span: Span) -> Result<(), ScrapError> {
+        let place = self.resolve(target, span)?;
+        self.write_place(place, Write::Set(value), span)
+    }
 
+    /// Reads `target` for `??` and `??=`: a variable, property or array element
+    /// that doesn't exist gives None instead of an error.
+    fn read_optional(&mut self, target: &Expr) -> Result<Option<obj>, ScrapError> {
+        Ok(match &target.kind {
+            ExprKind::Literal(obj::Identifier(name)) => self.lookup(name),
+            ExprKind::Get {object, name} => match self.read_optional(object)? {
+                Some(obj::Instance(instance)) => instance.borrow().fields.get(&name.literal).cloned(),
+                _ => None
             },
-            Expr::Literal(val) => {
-                return val.clone()
+            ExprKind::Index {object, index: Some(index), ..} => match self.read_optional(object)? {
+                Some(container) => {
+                    let index = index.evaluate(self)?;
+                    read_element(&container, &index).ok()
+                }
+                None => None
             },
-            Expr::Unary {operator,right} => {
-                let right = right.evaluate(interpreter);
-                match right {
-                    obj::Num(n) => {
-                        match operator.ttype {
-                            TType::Minus => {
-                                obj::Num(-n)
-                            },
-                            _ => {
-                                println!("not a unary operator");
-                                obj::Null
-                            }
-                        }
-                    },
-                    obj::Bool(b) => {
-                        match operator.ttype {
-                            TType::Bang => {
-                                if b == true {
-                                    obj::Bool(false)
-                                } else {
-                                    obj::Bool(true)
-                                }
-
-                            },
-                            _ => {
-                                println!("not a unary operator");
-                                obj::Null
-                            }
-                        }
-                    },
-                    _ => {
-                        ScrapError::error(
-                            InvalidSyntax,
-                            "unable to make unary",
-                            operator.line, file!()
-                        );
-                        obj::Null
-                    }
+            _ => Some(target.evaluate(self)?)
+        })
+    }
+
+    fn unset(&mut self, target: &Expr, span: Span) -> Result<(), ScrapError> {
+        match &target.kind {
+            ExprKind::Literal(obj::Identifier(name)) => {
+                self.environment.borrow_mut().remove(name);
+            }
+            ExprKind::Index {..} => {
+                let place = self.resolve(target, span)?;
+                self.write_place(place, Write::Unset, span)?;
+            }
+            ExprKind::Get {object, name} => {
+                if let obj::Instance(instance) = object.evaluate(self)? {
+                    instance.borrow_mut().fields.remove(&name.literal);
                 }
+            }
+            _ => {
+                return Err(ScrapError::error(RuntimeError, "can't unset this expression", span));
+            }
+        }
+        Ok(())
+    }
+
+    fn set_property(&mut self, object: obj, name: &Token, value: obj) -> Result<(), ScrapError> {
+        match object {
+            obj::Instance(instance) => {
+                instance.borrow_mut().fields.insert(name.literal.clone(), value);
+                Ok(())
+            }
+            _ => Err(ScrapError::error(
+                RuntimeError,
+                format!("can't set property '{}' on a non-object", name.literal).as_str(),
+                name.span
+            ))
+        }
+    }
+
+    /// Applies the arithmetic, comparison or string `operator` to two values,
+    /// juggling their types the way PHP 8 does. Compound assignments use it too,
+    /// with the operator they stand for.
+    fn binary_op(&mut self, operator: &TType, left: obj, right: obj, span: Span) -> Result<obj, ScrapError> {
+        Ok(match operator {
+            TType::Dot => match (left.to_php_string(), right.to_php_string()) {
+                (Some(s1), Some(s2)) => obj::Str(s1 + &s2),
+                _ => {
+                    return Err(ScrapError::error(
+                        RuntimeError,
+                        format!("can't concatenate a {} and a {}", left.type_name(), right.type_name()).as_str(),
+                        span
+                    ));
+                }
+            },
+            TType::EqualEqual => obj::Bool(left.loose_eq(&right)),
+            TType::BangEqual => obj::Bool(!left.loose_eq(&right)),
+            TType::EqualEqualEqual => obj::Bool(left.strict_eq(&right)),
+            TType::BangEqualEqual => obj::Bool(!left.strict_eq(&right)),
+            TType::Less | TType::LessEqual | TType::Greater | TType::GreaterEqual => {
+                obj::Bool(comparison_holds(operator, left.compare(&right)))
+            }
+            // values that can't be ordered count as greater, as in PHP
+            TType::Spaceship => obj::Int(match left.compare(&right) {
+                Some(Ordering::Less) => -1,
+                Some(Ordering::Equal) => 0,
+                _ => 1
+            }),
+            TType::Plus if matches!(
+                (&left, &right),
+                (obj::Array(_) | obj::Map(_), obj::Array(_) | obj::Map(_))
+            ) => left.union(&right),
+            _ => match (left.to_number(), right.to_number()) {
+                (Some(a), Some(b)) => self.numeric_op(operator, a, b, span)?,
+                _ => {
+                    let msg = format!(
+                        "Unsupported operand types: {} {} {}",
+                        left.type_name(),
+                        operator_symbol(operator),
+                        right.type_name()
+                    );
+                    return Err(self.throw("TypeError", msg, span));
+                }
+            }
+        })
+    }
+
+    /// Applies `operator` to two numbers. Integer results that don't fit in 64
+    /// bits become floats, and `/` gives an integer only when it divides exactly.
+    fn numeric_op(&mut self, operator: &TType, left: obj, right: obj, span: Span) -> Result<obj, ScrapError> {
+        use TType::{Ampersand, Caret, GreaterGreater, LessLess, Percent, Pipe};
+        // like PHP, `%` and the bitwise operators work on the integer parts of their operands
+        if matches!(operator, Percent | Ampersand | Pipe | Caret | LessLess | GreaterGreater) {
+            let (a, b) = (left.to_int(), right.to_int());
+            return Ok(obj::Int(match operator {
+                Percent if b == 0 => {
+                    return Err(self.throw("DivisionByZeroError", "Modulo by zero".to_string(), span));
+                }
+                Percent => a.wrapping_rem(b),
+                Ampersand => a & b,
+                Pipe => a | b,
+                Caret => a ^ b,
+                _ if b < 0 => {
+                    return Err(self.throw("ArithmeticError", "Bit shift by negative number".to_string(), span));
+                }
+                _ => shift_bits(operator, a, b)
+            }));
+        }
+        if let (obj::Int(a), obj::Int(b)) = (&left, &right) {
+            let exact = match operator {
+                TType::Plus => a.checked_add(*b),
+                TType::Minus => a.checked_sub(*b),
+                TType::Star => a.checked_mul(*b),
+                TType::Slash if *b != 0 && a.checked_rem(*b) == Some(0) => a.checked_div(*b),
+                TType::StarStar => u32::try_from(*b).ok().and_then(|b| a.checked_pow(b)),
+                _ => None
+            };
+            if let Some(result) = exact {
+                return Ok(obj::Int(result));
+            }
+        }
+        let (a, b) = (left.to_float(), right.to_float());
+        Ok(match operator {
+            TType::Plus => obj::Float(a + b),
+            TType::Minus => obj::Float(a - b),
+            TType::Star => obj::Float(a * b),
+            TType::Slash if b == 0.0 => {
+                return Err(self.throw("DivisionByZeroError", "Division by zero".to_string(), span));
+            }
+            TType::Slash => obj::Float(a / b),
+            TType::StarStar => obj::Float(a.powf(b)),
+            _ => {
+                return Err(ScrapError::error(
+                    EvaluatorError,
+                    "undefined binary operator",
+                    span
+                ));
+            }
+        })
+    }
+
+    fn call_builtin(&mut self, builtin: &Builtin, arguments: Vec<obj>, span: Span) -> Result<obj, ScrapError> {
+        if !builtin.arity.contains(&arguments.len()) {
+            return Err(ScrapError::error(
+                RuntimeError,
+                format!(
+                    "{}() expects {} arguments, {} given",
+                    builtin.name,
+                    describe_arity(&builtin.arity),
+                    arguments.len()
+                ).as_str(),
+                span
+            ));
+        }
+        match (builtin.function)(self, arguments) {
+            Ok(value) => Ok(value),
+            Err(BuiltinError::Error(msg)) => Err(ScrapError::error(RuntimeError, msg.as_str(), span)),
+            Err(BuiltinError::Throw(class, msg)) => Err(self.throw(class, msg, span))
+        }
+    }
+
+    fn call(&mut self, function: &Function, arguments: Vec<obj>, span: Span) -> Result<obj, ScrapError> {
+        // every parameter up to the last one without a default is required
+        let required = function.params
+            .iter()
+            .rposition(|(_, default)| default.is_none())
+            .map_or(0, |last| last + 1);
+        let arity = required..=function.params.len();
+        if !arity.contains(&arguments.len()) {
+            return Err(ScrapError::error(
+                RuntimeError,
+                format!(
+                    "{}() expects {} arguments, {} given",
+                    function.name,
+                    describe_arity(&arity),
+                    arguments.len()
+                ).as_str(),
+                span
+            ));
+        }
+        if self.call_stack.len() >= MAX_CALL_DEPTH {
+            return Err(ScrapError::error(
+                RuntimeError,
+                format!("maximum call depth of {MAX_CALL_DEPTH} reached in {}()", function.name).as_str(),
+                span
+            ).with_help("check for recursion that never stops".to_string()));
+        }
+        let mut environment = Environment::with_enclosing(Rc::clone(&function.closure));
+        let mut arguments = arguments.into_iter();
+        for (param, default) in &function.params {
+            let value = match (arguments.next(), default) {
+                (Some(argument), _) => argument,
+                (None, Some(default)) => default.evaluate(self)?,
+                (None, None) => obj::Null
+            };
+            environment.define(param.literal.clone(), value);
+        }
+        self.call_stack.push((function.name.clone(), span.line));
+        let flow = self.execute_block(&function.body, environment);
+        self.call_stack.pop();
+        match flow? {
+            Flow::Return(value) => Ok(value),
+            _ => Ok(obj::Null)
+        }
+    }
+
+    fn evaluate_variable(&self, name: &str, span: Span) -> Result<obj, ScrapError> {
+        // a function's name used as a value gives the function, so it can be passed around
+        match self.lookup(name).or_else(|| self.functions.get(name).cloned()) {
+            Some(value) => Ok(value),
+            None => Err(self.undefined_variable(name, span))
+        }
+    }
+
+    fn undefined_variable(&self, name: &str, span: Span) -> ScrapError {
+        let names = self.environment.borrow().names();
+        self.undefined(format!("undefined variable '{name}'"), name, names, span)
+    }
+
+    #[inline(never)]
+    fn evaluate_assign(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Result<obj, ScrapError> {
+        if operator.ttype == TType::Equal {
+            let value = right.evaluate(self)?;
+            self.store(left, value.clone(), operator.span)?;
+            return Ok(value);
+        }
+        // the target is resolved once, so its index expressions run once
+        let place = self.resolve(left, operator.span)?;
+        let value = match operator.ttype {
+            // the right side is only evaluated when the target is unset or null
+            TType::QuestionQuestionEqual => match self.read_place(&place) {
+                Ok(current) if current != obj::Null => return Ok(current),
+                _ => right.evaluate(self)?
             },
             _ => {
-                ScrapError::error(InvalidSyntax, "unimplemented", 0, file!());
-                obj::Null
+                let current = self.read_place(&place)?;
+                let right = right.evaluate(self)?;
+                self.binary_op(&compound_operator(&operator.ttype), current, right, operator.span)?
             }
+        };
+        self.write_place(place, Write::Set(value.clone()), operator.span)?;
+        Ok(value)
+    }
 
+    #[inline(never)]
+    fn evaluate_increment(&mut self, target: &Expr, operator: &Token, prefix: bool) -> Result<obj, ScrapError> {
+        let place = self.resolve(target, operator.span)?;
+        let current = self.read_place(&place)?;
+        let increment = operator.ttype == TType::PlusPlus;
+        let step = |number: &obj| match (number, increment) {
+            (obj::Int(n), true) => n.checked_add(1).map_or(obj::Float(*n as f64 + 1.0), obj::Int),
+            (obj::Int(n), false) => n.checked_sub(1).map_or(obj::Float(*n as f64 - 1.0), obj::Int),
+            (obj::Float(n), true) => obj::Float(n + 1.0),
+            (obj::Float(n), _) => obj::Float(n - 1.0),
+            (other, _) => other.clone()
+        };
+        let updated = match (&current, increment) {
+            (obj::Int(_) | obj::Float(_), _) => step(&current),
+            // like PHP, incrementing null gives 1 and decrementing it leaves it null
+            (obj::Null, true) => obj::Int(1),
+            (obj::Null, false) => obj::Null,
+            // and bools are left as they are
+            (obj::Bool(_), _) => current.clone(),
+            // numeric strings become numbers; "" goes to "1" or -1, other strings
+            // step on their last letter or digit and don't decrement
+            (obj::Str(s), _) => match numeric_string(s) {
+                Some(number) => step(&number),
+                None if s.is_empty() && increment => obj::Str("1".to_string()),
+                None if s.is_empty() => obj::Int(-1),
+                None if increment => obj::Str(increment_string(s)),
+                None => current.clone()
+            },
+            (other, _) => {
+                let action = if increment { "increment" } else { "decrement" };
+                return Err(ScrapError::error(
+                    RuntimeError,
+                    format!("can't {action} a {}", other.type_name()).as_str(),
+                    operator.span
+                ));
+            }
+        };
+        self.write_place(place, Write::Set(updated.clone()), operator.span)?;
+        if prefix {
+            Ok(updated)
+        } else {
+            Ok(current)
         }
+    }
 
+    #[inline(never)]
+    fn evaluate_binary(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Result<obj, ScrapError> {
+        if operator.ttype == TType::QuestionQuestion {
+            return match self.read_optional(left)? {
+                Some(value) if value != obj::Null => Ok(value),
+                _ => right.evaluate(self)
+            };
+        }
+        let left = left.evaluate(self)?;
+        // `&&` and `||` only evaluate their right side when it decides the result
+        match operator.ttype {
+            TType::And if !left.is_truthy() => return Ok(obj::Bool(false)),
+            TType::Or if left.is_truthy() => return Ok(obj::Bool(true)),
+            TType::And | TType::Or => return Ok(obj::Bool(right.evaluate(self)?.is_truthy())),
+            _ => {}
+        }
+        let right = match (&operator.ttype, &right.kind) {
+            // a name on the right of `instanceof` is looked up among the classes first
+            (TType::Instanceof, ExprKind::Literal(obj::Identifier(name))) => match self.classes.get(name) {
+                Some(class) => class.clone(),
+                None => right.evaluate(self)?
+            },
+            _ => right.evaluate(self)?
+        };
+        if operator.ttype == TType::Instanceof {
+            return match (left, right) {
+                (obj::Instance(instance), obj::Class(class)) => {
+                    Ok(obj::Bool(instance.borrow().class.is_subclass_of(&class)))
+                }
+                (obj::Instance(instance), obj::Interface(interface)) => {
+                    Ok(obj::Bool(instance.borrow().class.implements(&interface)))
+                }
+                (_, obj::Class(_) | obj::Interface(_)) => Ok(obj::Bool(false)),
+                _ => Err(ScrapError::error(
+                    RuntimeError,
+                    "right side of 'instanceof' must be a class or interface",
+                    operator.span
+                ))
+            };
+        }
+        self.binary_op(&operator.ttype, left, right, operator.span)
     }
-}
 
-impl Stmt {
-    pub fn run_stmt(stmt: Stmt, interpreter: &mut Interpreter) {
-        match stmt {
-            Stmt::Print(statement) => {
-                match *statement {
-                    Stmt::Expression(expression) => {
-                        let val = expression.evaluate(interpreter);
-                        match val {
-                            obj::Num(n) => {
-                                println!("{n}");
-                            }
-                            obj::Bool(b) => {
-                                println!("{b}");
-
-                            }
-                            obj::Str(s) => {
-                                println!("{}", s);
-
-                            }
-                            obj::Null => {
-                                println!("Null");
-
-                            }
-                            obj::Identifier(i) => {
-                                println!("{}", interpreter.variables.get(i.as_str()).unwrap());
-                            }
-                        }
-                    },
-                    Stmt::VariableCall {identifier} => {
-                        let identifierobject = identifier.evaluate(interpreter);
-                        let mut id = String::new();
-
-
-                        match identifierobject {
-                            obj::Identifier(identifier) => {
-                                id = identifier;
-                            }
-                            _ => {
-                                ScrapError::error(
-                                    EvaluatorError,
-                                    "not an identifier",
-                                    line!() as usize,
-                                    file!()
-                                )
-                            }
-                        }
-                        let var = interpreter.variables.get(&*id).unwrap();
-                        match var {
-                            obj::Num(n) => {
-                                println!("{n}");
-                            }
-                            obj::Bool(b) => {
-                                println!("{b}");
-
-                            }
-                            obj::Str(s) => {
-                                println!("{}", s);
-
-                            }
-                            obj::Null => {
-                                println!("Null");
-
-                            }
-                            obj::Identifier(i) => {
-                                print!("{}", interpreter.variables.get(i.as_str()).unwrap());
-                            }
-                            _ => {}
-                        }
+    #[inline(never)]
+    fn evaluate_unary(&mut self, operator: &Token, right: &Expr) -> Result<obj, ScrapError> {
+        let right = right.evaluate(self)?;
+        Ok(match operator.ttype {
+            TType::Bang => obj::Bool(!right.is_truthy()),
+            // like PHP, `+x` is `x * 1` and `-x` is `x * -1`, juggling `x` to a number
+            TType::Plus | TType::Minus => match (&operator.ttype, right.to_number()) {
+                (TType::Plus, Some(number)) => number,
+                (_, Some(obj::Int(n))) => n.checked_neg().map_or(obj::Float(-(n as f64)), obj::Int),
+                (_, Some(number)) => obj::Float(-number.to_float()),
+                (_, None) => {
+                    let msg = format!("Unsupported operand types: {} * int", right.type_name());
+                    return Err(self.throw("TypeError", msg, operator.span));
+                }
+            },
+            _ => match right {
+                obj::Int(n) => obj::Int(!n),
+                obj::Float(n) => obj::Int(!(n as i64)),
+                _ => {
+                    let msg = format!("Cannot perform bitwise not on {}", right.type_name());
+                    return Err(self.throw("TypeError", msg, operator.span));
+                }
+            }
+        })
+    }
 
-                    }
-                    _ => {
-                        ScrapError::error(
-                            InvalidSyntax,
-                            "unable to print statement",
-                                line!() as usize,
-                            file!()
-                        )
+    #[inline(never)]
+    fn evaluate_match(
+        &mut self,
+        keyword: &Token,
+        subject: &Expr,
+        arms: &[(Vec<Expr>, Expr)],
+        default: Option<&Expr>
+    ) -> Result<obj, ScrapError> {
+        let subject = subject.evaluate(self)?;
+        for (conditions, result) in arms {
+            for condition in conditions {
+                if condition.evaluate(self)?.strict_eq(&subject) {
+                    return result.evaluate(self);
+                }
+            }
+        }
+        match default {
+            Some(default) => default.evaluate(self),
+            None => {
+                let case = match &subject {
+                    obj::Str(s) => format!("'{s}'"),
+                    other => other.to_string()
+                };
+                let message = format!("Unhandled match case {case}");
+                Err(self.throw("UnhandledMatchError", message, keyword.span))
+            }
+        }
+    }
+
+    #[inline(never)]
+    fn evaluate_array(&mut self, elements: &[(Option<Expr>, Expr)], span: Span) -> Result<obj, ScrapError> {
+        if elements.iter().all(|(key, _)| key.is_none()) {
+            return Ok(obj::Array(
+                elements.iter().map(|(_, element)| element.evaluate(self)).collect::<Result<_, _>>()?
+            ));
+        }
+        let mut map = OrderedMap::new();
+        for (key, element) in elements {
+            let key = key.as_ref().map(|key| key.evaluate(self)).transpose()?;
+            let element = element.evaluate(self)?;
+            match key.as_ref().map(Key::from_obj) {
+                Some(Ok(key)) => map.insert(key, element),
+                Some(Err(msg)) => {
+                    return Err(ScrapError::error(RuntimeError, msg.as_str(), span));
+                }
+                None => {
+                    if let Err(msg) = map.push(element) {
+                        return Err(ScrapError::error(RuntimeError, msg.as_str(), span));
                     }
                 }
+            }
+        }
+        Ok(obj::Map(map))
+    }
 
+    #[inline(never)]
+    fn evaluate_index(&mut self, object: &Expr, bracket: &Token, index: Option<&Expr>) -> Result<obj, ScrapError> {
+        let object = object.evaluate(self)?;
+        let index = match index {
+            Some(index) => index.evaluate(self)?,
+            None => {
+                return Err(ScrapError::error(
+                    RuntimeError,
+                    "cannot use [] for reading",
+                    bracket.span
+                ));
+            }
+        };
+        read_element(&object, &index).map_err(|msg| ScrapError::error(RuntimeError, msg.as_str(), bracket.span))
+    }
+
+    #[inline(never)]
+    fn evaluate_parent(&mut self, keyword: &Token, method: &Token) -> Result<obj, ScrapError> {
+        let superclass = self.lookup("parent");
+        let this = self.lookup("this");
+        match (superclass, this) {
+            (Some(obj::Class(superclass)), Some(this)) => match superclass.find_method(&method.literal) {
+                Some(function) => Ok(obj::Function(Rc::new(function.bind(this)))),
+                None => Err(ScrapError::error(
+                    RuntimeError,
+                    format!("undefined method {}::{}", superclass.name, method.literal).as_str(),
+                    method.span
+                ))
             },
-            Stmt::VariableAssign {identifier, value} => {
-                let val = value.evaluate(interpreter);
-
-                if interpreter.variables.contains_key(&*identifier) {
-                    interpreter.variables.remove(&*identifier);
-                    interpreter.variables.insert(identifier,val);
-                } else {
-                    interpreter.variables.insert(identifier,val);
-                }
-            }
-            Stmt::VariableCall {identifier} => {
-                let identifierobject = identifier.evaluate(interpreter);
-                let mut id = String::new();
-                match identifierobject {
-                    obj::Identifier(identifier) => {
-                        id = identifier;
+            _ => Err(ScrapError::error(
+                RuntimeError,
+                "can't use 'parent' outside of a subclass method",
+                keyword.span
+            ))
+        }
+    }
+
+    #[inline(never)]
+    fn evaluate_new(&mut self, class: &Token, arguments: &[Expr]) -> Result<obj, ScrapError> {
+        let arguments: Vec<obj> = arguments
+            .iter()
+            .map(|argument| argument.evaluate(self))
+            .collect::<Result<_, _>>()?;
+        match self.classes.get(&class.literal).cloned() {
+            Some(obj::Class(class_obj)) => self.instantiate(class_obj, arguments, class.span),
+            _ => Err(self.undefined(
+                format!("class '{}' not found", class.literal),
+                &class.literal,
+                self.classes.keys().cloned().collect(),
+                class.span
+            ))
+        }
+    }
+
+    #[inline(never)]
+    fn evaluate_call(&mut self, callee: &Expr, paren: &Token, arguments: &[Expr]) -> Result<obj, ScrapError> {
+        let callee = match &callee.kind {
+            // a name is looked up among the functions first, so a variable can't hide one
+            ExprKind::Literal(obj::Identifier(name)) => match self.functions.get(name) {
+                Some(function) => function.clone(),
+                None => match self.lookup(name) {
+                    Some(value) => value,
+                    None => {
+                        let names = self.functions.keys().cloned().collect();
+                        return Err(self.undefined(format!("undefined function '{name}'"), name, names, callee.span));
                     }
-                    _ => {
-                        ScrapError::error(
-                            EvaluatorError,
-                            "not an identifier",
-                            line!() as usize,
-                            file!()
-                        )
+                }
+            },
+            _ => callee.evaluate(self)?
+        };
+        let arguments: Vec<obj> = arguments
+            .iter()
+            .map(|argument| argument.evaluate(self))
+            .collect::<Result<_, _>>()?;
+        match callee {
+            obj::Function(function) => self.call(&function, arguments, paren.span),
+            obj::Builtin(builtin) => self.call_builtin(&builtin, arguments, paren.span),
+            _ => Err(ScrapError::error(
+                RuntimeError,
+                "can only call functions",
+                paren.span
+            ))
+        }
+    }
+
+    #[inline(never)]
+    fn execute_print(&mut self, statement: &Stmt) -> Result<(), ScrapError> {
+        match &statement.kind {
+            StmtKind::Expression(expression) => {
+                let val = expression.evaluate(self)?;
+                match &val {
+                    obj::Array(_) | obj::Map(_) => println!("{val}"),
+                    _ => println!("{}", val.to_php_string().unwrap_or_else(|| val.to_string()))
+                }
+                Ok(())
+            },
+            _ => Err(ScrapError::error(
+                InvalidSyntax,
+                "unable to print statement",
+                statement.span
+            ))
+        }
+    }
+
+    #[inline(never)]
+    fn execute_for(
+        &mut self,
+        initializer: Option<&Stmt>,
+        condition: Option<&Expr>,
+        increment: Option<&Expr>,
+        block: &Stmt
+    ) -> Result<Flow, ScrapError> {
+        // the initializer's variables are local to the loop, and each iteration
+        // gets its own copy of them, so closures made in the body keep their values
+        let mut scope = Rc::new(RefCell::new(Environment::with_enclosing(Rc::clone(&self.environment))));
+        if let Some(initializer) = initializer {
+            self.with_scope(Rc::clone(&scope), |interpreter| Stmt::run_stmt(initializer, interpreter))?;
+        }
+        loop {
+            let exit = self.with_scope(Rc::clone(&scope), |interpreter| {
+                if let Some(condition) = condition {
+                    if !condition.evaluate(interpreter)?.is_truthy() {
+                        return Ok(Some(Flow::Normal));
                     }
                 }
-                let _var = interpreter.variables.get(&*id).unwrap();
+                Ok(Stmt::run_stmt(block, interpreter)?.exit_loop())
+            })?;
+            if let Some(exit) = exit {
+                return Ok(exit);
+            }
+            let next = scope.borrow().clone();
+            scope = Rc::new(RefCell::new(next));
+            if let Some(increment) = increment {
+                self.with_scope(Rc::clone(&scope), |interpreter| increment.evaluate(interpreter))?;
+            }
+        }
+    }
+
+    #[inline(never)]
+    fn execute_foreach(
+        &mut self,
+        keyword: &Token,
+        iterable: &Expr,
+        key: Option<&Token>,
+        value: &Token,
+        block: &Stmt
+    ) -> Result<Flow, ScrapError> {
+        // iterate over a copy, like PHP's by-value foreach
+        let entries: Vec<(obj, obj)> = match iterable.evaluate(self)? {
+            obj::Array(items) => items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| (obj::Int(i as i64), item))
+                .collect(),
+            obj::Map(map) => map
+                .iter()
+                .map(|(key, item)| (key.to_obj(), item.clone()))
+                .collect(),
+            other => {
+                return Err(ScrapError::error(
+                    RuntimeError,
+                    format!("foreach() argument must be an array, {} given", other.type_name()).as_str(),
+                    keyword.span
+                ));
+            }
+        };
+        for (entry_key, entry_value) in entries {
+            // the loop variables live in a new scope around the body on each
+            // iteration, so closures made in the body keep their values
+            let mut scope = Environment::with_enclosing(Rc::clone(&self.environment));
+            if let Some(key) = key {
+                scope.define(key.literal.clone(), entry_key);
             }
-            Stmt::Expression(expression) => {
-                expression.evaluate(interpreter);
+            scope.define(value.literal.clone(), entry_value);
+            let flow = self.with_scope(Rc::new(RefCell::new(scope)), |interpreter| Stmt::run_stmt(block, interpreter))?;
+            if let Some(exit) = flow.exit_loop() {
+                return Ok(exit);
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    #[inline(never)]
+    fn execute_switch(&mut self, subject: &Expr, cases: &[(Option<Expr>, Vec<Stmt>)]) -> Result<Flow, ScrapError> {
+        let subject = subject.evaluate(self)?;
+        let mut matched = None;
+        for (i, (value, _)) in cases.iter().enumerate() {
+            if let Some(value) = value {
+                if value.evaluate(self)?.loose_eq(&subject) {
+                    matched = Some(i);
+                    break;
+                }
+            }
+        }
+        let start = matched.or_else(|| cases.iter().position(|(value, _)| value.is_none()));
+        let Some(start) = start else {
+            return Ok(Flow::Normal);
+        };
+        // run from the matching case to the end, falling through until a break
+        let scope = Environment::with_enclosing(Rc::clone(&self.environment));
+        self.with_scope(Rc::new(RefCell::new(scope)), |interpreter| {
+            for stmt in cases[start..].iter().flat_map(|(_, stmts)| stmts) {
+                let result = Stmt::run_stmt(stmt, interpreter)?;
+                if result != Flow::Normal {
+                    // `continue` inside a switch acts like `break`
+                    return Ok(result.exit_loop().unwrap_or(Flow::Normal));
+                }
+            }
+            Ok(Flow::Normal)
+        })
+    }
+
+    #[inline(never)]
+    fn execute_throw(&mut self, keyword: &Token, value: &Expr) -> Result<Flow, ScrapError> {
+        let value = value.evaluate(self)?;
+        let throwable = match &value {
+            obj::Instance(instance) => self.throwable
+                .as_ref()
+                .is_some_and(|throwable| instance.borrow().class.implements(throwable)),
+            _ => false
+        };
+        if !throwable {
+            return Err(ScrapError::error(
+                RuntimeError,
+                format!("can only throw objects that implement Throwable, {} given", value.type_name()).as_str(),
+                keyword.span
+            ));
+        }
+        Err(ScrapError::exception(value, keyword.span))
+    }
+
+    #[inline(never)]
+    fn execute_try(
+        &mut self,
+        block: &[Stmt],
+        catches: &[(Token, Option<Token>, Vec<Stmt>)],
+        finally: Option<&[Stmt]>
+    ) -> Result<Flow, ScrapError> {
+        let environment = Environment::with_enclosing(Rc::clone(&self.environment));
+        let mut result = self.execute_block(block, environment);
+        if let Err(ScrapError {thrown: Some(exception), ..}) = &result {
+            let exception = exception.as_ref().clone();
+            let handler = catches
+                .iter()
+                .find(|(class, _, _)| self.is_caught_by(&exception, class));
+            if let Some((_, variable, handler)) = handler {
+                let mut environment = Environment::with_enclosing(Rc::clone(&self.environment));
+                if let Some(variable) = variable {
+                    environment.define(variable.literal.clone(), exception);
+                }
+                result = self.execute_block(handler, environment);
+            }
+        }
+        if let Some(finally) = finally {
+            // a `return`, `break` or exception in the finally block replaces whatever came before
+            let environment = Environment::with_enclosing(Rc::clone(&self.environment));
+            let flow = self.execute_block(finally, environment)?;
+            if flow != Flow::Normal {
+                return Ok(flow);
+            }
+        }
+        result
+    }
+}
+impl Expr {
+    /// Recursive scripts nest a frame of this function for every call they make,
+    /// so it only dispatches: the bigger arms live in `Interpreter::evaluate_*`,
+    /// kept out of line so their locals don't grow this frame.
+    fn evaluate(&self, interpreter: &mut Interpreter) -> Result<obj, ScrapError> {
+        interpreter.check_stack(self.span)?;
+        match &self.kind {
+            ExprKind::Grouping(expr) => expr.evaluate(interpreter),
+            ExprKind::Assign {left,operator,right} => interpreter.evaluate_assign(left, operator, right),
+            ExprKind::Increment {target, operator, prefix} => interpreter.evaluate_increment(target, operator, *prefix),
+            ExprKind::Binary {left,operator,right} => interpreter.evaluate_binary(left, operator, right),
+            ExprKind::Literal(obj::Identifier(name)) => interpreter.evaluate_variable(name, self.span),
+            ExprKind::Literal(val) => Ok(val.clone()),
+            ExprKind::Unary {operator,right} => interpreter.evaluate_unary(operator, right),
+            ExprKind::Match {keyword, subject, arms, default} => {
+                interpreter.evaluate_match(keyword, subject, arms, default.as_deref())
+            }
+            ExprKind::Closure {params, body, arrow} => Ok(obj::Function(interpreter.make_closure(params, body, *arrow))),
+            ExprKind::Array(elements) => interpreter.evaluate_array(elements, self.span),
+            ExprKind::Index {object, bracket, index} => interpreter.evaluate_index(object, bracket, index.as_deref()),
+            ExprKind::Get {object, name} => {
+                let object = object.evaluate(interpreter)?;
+                interpreter.get_property(object, name)
+            }
+            ExprKind::Parent {keyword, method} => interpreter.evaluate_parent(keyword, method),
+            ExprKind::New {class, arguments} => interpreter.evaluate_new(class, arguments),
+            ExprKind::Call {callee, paren, arguments} => interpreter.evaluate_call(callee, paren, arguments)
+        }
+    }
+}
+
+impl Stmt {
+    /// Like `Expr::evaluate`, this leaves the bigger statements to out of line
+    /// `Interpreter` methods to keep its frame small.
+    pub fn run_stmt(stmt: &Stmt, interpreter: &mut Interpreter) -> Result<Flow, ScrapError> {
+        interpreter.check_stack(stmt.span)?;
+        match &stmt.kind {
+            StmtKind::Print(statement) => interpreter.execute_print(statement)?,
+            StmtKind::VariableAssign {identifier, value} => {
+                let val = value.evaluate(interpreter)?;
+                interpreter.define(identifier.clone(), val);
+            }
+            StmtKind::Expression(expression) => {
+                expression.evaluate(interpreter)?;
             },
-            Stmt::Ifstmt {expr, block, elseblock} => {
-                let expression = expr.evaluate(interpreter);
-                match expression {
-                    obj::Bool(b) => {
-                        if b == true {
-                            match *block {
-                                Stmt::Block(stmts) => {
-                                    for stmt in stmts {
-                                        Stmt::run_stmt(stmt,interpreter);
-                                    }
-                                }
-                                _ => {
-                                    ScrapError::error(
-                                        InvalidSyntax,
-                                        "exected block",
-                                        line!() as usize,
-                                        file!()
-                                    )
-                                }
-                            }
-
-                        } else if elseblock.is_some() && b == false {
-                            match *elseblock.unwrap() {
-                                Stmt::Block(stmts) => {
-                                    for stmt in stmts {
-                                        Stmt::run_stmt(stmt,interpreter);
-                                    }
-                                }
-                                _ => {
-                                    ScrapError::error(
-                                        InvalidSyntax,
-                                        "exected block",
-                                        line!() as usize,
-                                        file!()
-                                    )
-                                }
-                            }
-                        }
-                    }
-                    _ => {
-                        ScrapError::error(
-                            EvaluatorError,
-                            "can't perform any other operation in if statement than comparison",
-                            line!() as usize,
-                            file!()
-                        )
+            StmtKind::Ifstmt {branches, elseblock} => {
+                for (expr, block) in branches {
+                    if expr.evaluate(interpreter)?.is_truthy() {
+                        return Stmt::run_stmt(block, interpreter);
                     }
                 }
+                if let Some(elseblock) = elseblock {
+                    return Stmt::run_stmt(elseblock, interpreter);
+                }
             },
-            Stmt::WhileStmt {expr, block} => {
-                while expr.evaluate(interpreter) == obj::Bool(true) {
-                    match *block.clone() {
-                        Stmt::Block(b) => {
-                            for stmt in b {
-                                Stmt::run_stmt(stmt, interpreter)
-                            }
-                        }
-                        _ => {
-                            ScrapError::error(
-                                EvaluatorError,
-                                "expected bool",
-                                    line!() as usize,
-                                file!()
-                            )
-                        }
+            StmtKind::WhileStmt {expr, block} => {
+                while expr.evaluate(interpreter)?.is_truthy() {
+                    if let Some(flow) = Stmt::run_stmt(block, interpreter)?.exit_loop() {
+                        return Ok(flow);
                     }
                 }
             }
+            StmtKind::ForStmt {initializer, condition, increment, block} => {
+                return interpreter.execute_for(initializer.as_deref(), condition.as_deref(), increment.as_deref(), block);
+            }
+            StmtKind::Foreach {keyword, iterable, key, value, block} => {
+                return interpreter.execute_foreach(keyword, iterable, key.as_ref(), value, block);
+            }
+            StmtKind::Switch {subject, cases} => {
+                return interpreter.execute_switch(subject, cases);
+            }
+            StmtKind::Block(stmts) => {
+                let environment = Environment::with_enclosing(Rc::clone(&interpreter.environment));
+                return interpreter.execute_block(stmts, environment);
+            }
+            StmtKind::Function {..} | StmtKind::Class {..} | StmtKind::Interface {..} | StmtKind::Trait {..} => {
+                interpreter.declare(stmt)?;
+            }
+            StmtKind::Unset {keyword, targets} => {
+                for target in targets {
+                    interpreter.unset(target, keyword.span)?;
+                }
+            }
+            StmtKind::Throw {keyword, value} => {
+                return interpreter.execute_throw(keyword, value);
+            }
+            StmtKind::Try {block, catches, finally} => {
+                return interpreter.execute_try(block, catches, finally.as_deref());
+            }
+            StmtKind::Return(value) => {
+                return Ok(Flow::Return(value.evaluate(interpreter)?));
+            }
+            StmtKind::Break(depth) => {
+                return Ok(Flow::Break(*depth));
+            }
+            StmtKind::Continue(depth) => {
+                return Ok(Flow::Continue(*depth));
+            }
+        }
+        Ok(Flow::Normal)
+    }
 
-            _ => {
-                println!("UNIMPLEMENTED")
+    /// Runs `stmts` in order, stopping early when one of them returns, breaks or continues.
+    fn run_block(stmts: &[Stmt], interpreter: &mut Interpreter) -> Result<Flow, ScrapError> {
+        for stmt in stmts {
+            let flow = Stmt::run_stmt(stmt, interpreter)?;
+            if flow != Flow::Normal {
+                return Ok(flow);
             }
+        }
+        Ok(Flow::Normal)
+    }
+}
 
+
+/// Whether the comparison `operator` holds for two values ordered as `ordering`;
+/// none of them holds for values without an order, like NAN and a number.
+fn comparison_holds(operator: &TType, ordering: Option<Ordering>) -> bool {
+    match operator {
+        TType::Less => ordering == Some(Ordering::Less),
+        TType::LessEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        TType::Greater => ordering == Some(Ordering::Greater),
+        TType::GreaterEqual => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        _ => false
+    }
+}
+
+/// `value << shift` or `value >> shift`. Shifting by 64 bits or more leaves 0,
+/// or -1 when a negative number is shifted right, as in PHP.
+fn shift_bits(operator: &TType, value: i64, shift: i64) -> i64 {
+    match (operator, u32::try_from(shift).ok().filter(|shift| *shift < 64)) {
+        (TType::LessLess, Some(shift)) => value.wrapping_shl(shift),
+        (TType::LessLess, None) => 0,
+        (_, Some(shift)) => value >> shift,
+        (_, None) if value < 0 => -1,
+        (_, None) => 0
+    }
+}
+
+/// How an arithmetic operator is written, for error messages.
+fn operator_symbol(operator: &TType) -> &'static str {
+    match operator {
+        TType::Plus => "+",
+        TType::Minus => "-",
+        TType::Star => "*",
+        TType::Slash => "/",
+        TType::Percent => "%",
+        TType::StarStar => "**",
+        TType::Ampersand => "&",
+        TType::Pipe => "|",
+        TType::Caret => "^",
+        TType::LessLess => "<<",
+        TType::GreaterGreater => ">>",
+        _ => "?"
+    }
+}
+
+/// The operator a compound assignment applies, `+` for `+=` and so on.
+fn compound_operator(assignment: &TType) -> TType {
+    match assignment {
+        TType::PlusEqual => TType::Plus,
+        TType::MinusEqual => TType::Minus,
+        TType::StarEqual => TType::Star,
+        TType::SlashEqual => TType::Slash,
+        TType::PercentEqual => TType::Percent,
+        TType::StarStarEqual => TType::StarStar,
+        TType::DotEqual => TType::Dot,
+        TType::AmpersandEqual => TType::Ampersand,
+        TType::PipeEqual => TType::Pipe,
+        TType::CaretEqual => TType::Caret,
+        TType::LessLessEqual => TType::LessLess,
+        TType::GreaterGreaterEqual => TType::GreaterGreater,
+        other => other.clone()
+    }
+}
+
+/// A variable, property or array element to read or write, as found by `Interpreter::resolve`.
+struct Place {
+    base: PlaceBase,
+    /// the evaluated indices below the base, outermost first; None for the append form `[]`
+    indices: Vec<Option<obj>>,
+    /// where each index was written, for errors reading it
+    brackets: Vec<Span>
+}
+
+/// The variable or property a `Place` starts from.
+enum PlaceBase {
+    Variable(String, Span),
+    /// a property of the already evaluated object
+    Property(obj, Token)
+}
+
+/// What `write_nested` does with the element it finds.
+enum Write {
+    Set(obj),
+    Unset
+}
+
+/// Reads `container[index]`.
+fn read_element(container: &obj, index: &obj) -> Result<obj, String> {
+    let key = Key::from_obj(index)?;
+    let element = match (container, &key) {
+        (obj::Array(items), Key::Int(i)) => usize::try_from(*i).ok().and_then(|i| items.get(i)),
+        (obj::Array(_), Key::Str(_)) => None,
+        (obj::Map(map), _) => map.get(&key),
+        (other, _) => return Err(format!("cannot index into a {}", other.type_name()))
+    };
+    match (element, key) {
+        (Some(element), _) => Ok(element.clone()),
+        (None, Key::Int(i)) => Err(format!("undefined array key {i}")),
+        (None, Key::Str(s)) => Err(format!("undefined array key \"{s}\""))
+    }
+}
+
+/// Applies `write` at `indices` inside `slot`. Setting turns a null slot into
+/// an empty array; a None index appends. A list stays a list while it is written
+/// at positions 0..=len, any other key or an unset turns it into an ordered map.
+fn write_nested(slot: &mut obj, indices: &[Option<obj>], write: Write) -> Result<(), String> {
+    let Some((index, rest)) = indices.split_first() else {
+        return Ok(());
+    };
+    if *slot == obj::Null {
+        match write {
+            Write::Unset => return Ok(()),
+            Write::Set(_) => *slot = obj::Array(Vec::new())
         }
     }
-}
\ No newline at end of file
+    let key = index.as_ref().map(Key::from_obj).transpose()?;
+    let unsetting_here = rest.is_empty() && matches!(write, Write::Unset);
+
+    if let obj::Array(items) = slot {
+        let position = match &key {
+            None => Some(items.len()),
+            Some(Key::Int(i)) => usize::try_from(*i).ok().filter(|i| *i <= items.len()),
+            Some(Key::Str(_)) => None
+        };
+        match position {
+            Some(position) if !unsetting_here => {
+                if position == items.len() {
+                    if let Write::Unset = write {
+                        return Ok(());
+                    }
+                    items.push(obj::Null);
+                }
+                let element = &mut items[position];
+                return match write {
+                    Write::Set(value) if rest.is_empty() => {
+                        *element = value;
+                        Ok(())
+                    }
+                    _ => write_nested(element, rest, write)
+                };
+            }
+            _ => *slot = obj::Map(OrderedMap::from_list(std::mem::take(items)))
+        }
+    }
+
+    let map = match slot {
+        obj::Map(map) => map,
+        other => return Err(format!("cannot use a {} as an array", other.type_name()))
+    };
+    let key = match (key, &write) {
+        (Some(key), _) => key,
+        (None, Write::Set(_)) => map.push(obj::Null)?,
+        (None, Write::Unset) => return Err("cannot use [] for unsetting".to_string())
+    };
+    if rest.is_empty() {
+        match write {
+            Write::Set(value) => map.insert(key, value),
+            Write::Unset => {
+                map.remove(&key);
+            }
+        }
+        return Ok(());
+    }
+    match map.get_mut(&key) {
+        Some(element) => write_nested(element, rest, write),
+        None => match write {
+            Write::Unset => Ok(()),
+            Write::Set(_) => {
+                map.insert(key.clone(), obj::Null);
+                write_nested(map.get_mut(&key).unwrap(), rest, write)
+            }
+        }
+    }
+}
+
+fn describe_arity(arity: &std::ops::RangeInclusive<usize>) -> String {
+    if arity.start() == arity.end() {
+        arity.start().to_string()
+    } else {
+        format!("{} to {}", arity.start(), arity.end())
+    }
+}
+
+/// The address of a local variable of the caller, which tells how deep the stack is.
+#[inline(always)]
+fn stack_address() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
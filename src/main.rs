@@ -3,69 +3,135 @@ mod token;
 mod tokentype;
 mod object;
 mod ast;
+mod decimal;
 mod error;
-// mod parser;
+mod environment;
+mod builtins;
+mod map;
 mod interpreter;
 mod parser;
+mod prelude;
+mod span;
 
 
 use std::env::args;
 use std::fs;
+use std::thread;
 use colored::Colorize;
 use crate::error::ScrapError;
-use crate::error::ScrapError::RuntimeError;
-use crate::interpreter::Interpreter;
+use crate::error::ErrorType::RuntimeError;
+use crate::interpreter::{Interpreter, STACK_SIZE};
 use crate::parser::Parser;
-// use crate::parser::Parser;
+use crate::prelude::PRELUDE;
 use crate::scanner::Scanner;
+use crate::span::{Span, PRELUDE_FILE, SCRIPT_FILE};
 use crate::token::Token;
 use std::time::Instant;
 
-
 fn main(){
 
     std::env::set_var("RUST_BACKTRACE", "5");
 
-    let input: Vec<String> = args().collect();
+    let mut input: Vec<String> = args().collect();
+    // with `--error-format=json` diagnostics are printed as JSON lines
+    let json = match input.iter().position(|arg| arg == "--error-format=json") {
+        Some(position) => {
+            input.remove(position);
+            true
+        }
+        None => false
+    };
     if input.len() < 3 {
-        error::ScrapError::error(ScrapError::RuntimeError, "too few arguments", 1, file!())
+        let error = ScrapError::error(RuntimeError, "too few arguments", Span::default());
+        emit(&error, &input[0], "", json);
+        std::process::exit(64);
     }
     if input[1] == "scrap" {
-        run_file(input[2].clone());
+        let path = input[2].clone();
+        // deep recursion needs more stack than the main thread has
+        let spawned = thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(move || run_file(&path, json));
+        let handle = match spawned {
+            Ok(handle) => handle,
+            Err(reason) => {
+                let error = ScrapError::error(
+                    RuntimeError,
+                    format!("unable to start the interpreter: {reason}").as_str(),
+                    Span::default()
+                );
+                emit(&error, &input[2], "", json);
+                std::process::exit(error.exit_code());
+            }
+        };
+        let result = handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+        if let Err(code) = result {
+            std::process::exit(code);
+        }
     }
-struct Scrap {}
+}
 
 
-fn run_file(source: String) {
+/// Reads and runs the script at `path`, reporting any errors. Fails with the
+/// exit code for the first error.
+fn run_file(path: &str, json: bool) -> Result<(), i32> {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(_) => {
+            let error = ScrapError::error(
+                RuntimeError,
+                format!("unable to read file {}", path).as_str(),
+                Span::default()
+            );
+            emit(&error, path, "", json);
+            return Err(error.exit_code());
+        }
+    };
+    if let Err(errors) = run(source.clone()) {
+        for error in &errors {
+            if error.span.file == PRELUDE_FILE {
+                emit(error, "<prelude>", PRELUDE, json);
+            } else {
+                emit(error, path, &source, json);
+            }
+        }
+        return Err(errors[0].exit_code());
+    }
+    Ok(())
+}
 
-    // println!("{:?}", source);
-    let input = fs::read_to_string(&source);
-    if input.is_err() {
-        ScrapError::error(
-            RuntimeError,
-            format!("unable to read file {}", source).as_str(),
-            line!() as usize,
-            file!()
-        )
+/// Prints `error` for people, or as a line of JSON for tools when `json` is set.
+fn emit(error: &ScrapError, filename: &str, source: &str, json: bool) {
+    if json {
+        eprintln!("{}", error.to_json(filename));
     } else {
-        run(input.unwrap())
+        error.report(filename, source);
     }
-
 }
-fn run(input: String) {
+
+/// Runs a script. Scanner and parser errors are all collected before
+/// reporting, a runtime error stops the script.
+fn run(input: String) -> Result<(), Vec<ScrapError>> {
     let now = Instant::now();
-    let mut scanner = Scanner::new(input);
-    scanner.scan_tokens();
+    let mut scanner = Scanner::new(input, SCRIPT_FILE);
+    let mut errors = scanner.scan_tokens().err().unwrap_or_default();
 
     let mut parser = Parser::new(scanner.tokens);
-    parser.parse();
+    if let Err(parse_errors) = parser.parse() {
+        errors.extend(parse_errors);
+    }
+    if !errors.is_empty() {
+        errors.sort_by_key(|error| error.span.start);
+        return Err(errors);
+    }
     /*for stmt in &parser.statements {
         let f = format!("\n{:#?}\n", stmt).yellow();
         println!("{}", f);
     };*/
 
-    let mut interpreter = Interpreter::new(parser.expressions, parser.statements);
-    interpreter.start();
+    let mut interpreter = Interpreter::new(parser.statements);
+    interpreter.start().map_err(|error| vec![error])?;
     let formatted_time = format!("{}", now.elapsed().as_secs_f64()).bright_magenta();
     println!("{formatted_time}");
-}}
\ No newline at end of file
+    Ok(())
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::object::obj;
+
+/// A key of an ordered map. PHP only has integer and string keys; every
+/// other value is converted with `Key::from_obj`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Key {
+    Int(i64),
+    Str(String),
+}
+
+impl Key {
+    /// Normalizes `value` the way PHP does: strings holding a canonical decimal
+    /// integer ("1" but not "01" or "1.5") become integer keys, floats are
+    /// truncated, booleans become 0 or 1 and null becomes "".
+    pub fn from_obj(value: &obj) -> Result<Key, String> {
+        match value {
+            obj::Str(s) => Ok(Key::from_str(s)),
+            obj::Int(n) => Ok(Key::Int(*n)),
+            obj::Float(n) => Ok(Key::Int(n.trunc() as i64)),
+            obj::Bool(b) => Ok(Key::Int(*b as i64)),
+            obj::Null => Ok(Key::Str(String::new())),
+            other => Err(format!("cannot use a {} as an array key", other.type_name())),
+        }
+    }
+
+    fn from_str(s: &str) -> Key {
+        let digits = s.strip_prefix('-').unwrap_or(s);
+        let canonical = !digits.is_empty()
+            && digits.chars().all(|c| c.is_ascii_digit())
+            && (digits == "0" || !digits.starts_with('0'))
+            && s != "-0";
+        match s.parse::<i64>() {
+            Ok(n) if canonical => Key::Int(n),
+            _ => Key::Str(s.to_string()),
+        }
+    }
+
+    pub fn to_obj(&self) -> obj {
+        match self {
+            Key::Int(n) => obj::Int(*n),
+            Key::Str(s) => obj::Str(s.clone()),
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Key::Int(n) => write!(f, "{n}"),
+            Key::Str(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+/// An insertion-ordered hash map, the value behind PHP's associative arrays.
+#[derive(Debug, Clone)]
+pub struct OrderedMap {
+    entries: Vec<(Key, obj)>,
+    /// position of each key in `entries`
+    positions: HashMap<Key, usize>,
+    /// the key `push` uses: one more than the largest integer key ever inserted,
+    /// or None once that key was the largest integer there is
+    next_index: Option<i64>,
+}
+
+impl OrderedMap {
+    pub fn new() -> OrderedMap {
+        OrderedMap {
+            entries: Vec::new(),
+            positions: HashMap::new(),
+            next_index: Some(0),
+        }
+    }
+
+    /// Builds a map with the keys 0, 1, 2... from a list.
+    pub fn from_list(items: Vec<obj>) -> OrderedMap {
+        let mut map = OrderedMap::new();
+        for (i, item) in items.into_iter().enumerate() {
+            map.insert(Key::Int(i as i64), item);
+        }
+        map
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn get(&self, key: &Key) -> Option<&obj> {
+        self.positions.get(key).map(|&position| &self.entries[position].1)
+    }
+
+    pub fn get_mut(&mut self, key: &Key) -> Option<&mut obj> {
+        let position = *self.positions.get(key)?;
+        Some(&mut self.entries[position].1)
+    }
+
+    /// Sets `key` to `value`, keeping the key's position if it already exists.
+    pub fn insert(&mut self, key: Key, value: obj) {
+        if let Some(slot) = self.get_mut(&key) {
+            *slot = value;
+            return;
+        }
+        if let (Key::Int(n), Some(next_index)) = (&key, self.next_index) {
+            if *n >= next_index {
+                self.next_index = n.checked_add(1);
+            }
+        }
+        self.positions.insert(key.clone(), self.entries.len());
+        self.entries.push((key, value));
+    }
+
+    /// Appends `value` under the next free integer key, like `$a[] = value`.
+    /// Fails when the largest integer key is already taken.
+    pub fn push(&mut self, value: obj) -> Result<Key, String> {
+        let Some(next_index) = self.next_index else {
+            return Err("cannot add element to the array as the next element is already occupied".to_string());
+        };
+        let key = Key::Int(next_index);
+        self.insert(key.clone(), value);
+        Ok(key)
+    }
+
+    pub fn remove(&mut self, key: &Key) -> Option<obj> {
+        let position = self.positions.remove(key)?;
+        let (_, value) = self.entries.remove(position);
+        for (key, _) in &self.entries[position..] {
+            *self.positions.get_mut(key).unwrap() -= 1;
+        }
+        Some(value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Key, &obj)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+}
+
+impl PartialEq for OrderedMap {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Key, OrderedMap};
+    use crate::object::obj;
+
+    fn keys(map: &OrderedMap) -> Vec<Key> {
+        map.iter().map(|(key, _)| key.clone()).collect()
+    }
+
+    #[test]
+    fn canonical_integer_strings_become_int_keys() {
+        assert_eq!(Key::from_str("1"), Key::Int(1));
+        assert_eq!(Key::from_str("0"), Key::Int(0));
+        assert_eq!(Key::from_str("-5"), Key::Int(-5));
+        assert_eq!(Key::from_str("9223372036854775807"), Key::Int(i64::MAX));
+    }
+
+    #[test]
+    fn other_strings_stay_string_keys() {
+        for s in ["01", "-0", "1.5", "", "-", " 1", "1 ", "+1", "9223372036854775808"] {
+            assert_eq!(Key::from_str(s), Key::Str(s.to_string()), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn remove_keeps_later_positions_in_step() {
+        let mut map = OrderedMap::new();
+        map.insert(Key::Str("a".to_string()), obj::Int(1));
+        map.insert(Key::Int(5), obj::Int(2));
+        map.insert(Key::Str("b".to_string()), obj::Int(3));
+        map.insert(Key::Int(7), obj::Int(4));
+
+        assert_eq!(map.remove(&Key::Int(5)), Some(obj::Int(2)));
+        assert_eq!(map.remove(&Key::Int(5)), None);
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&Key::Str("a".to_string())), Some(&obj::Int(1)));
+        assert_eq!(map.get(&Key::Str("b".to_string())), Some(&obj::Int(3)));
+        assert_eq!(map.get(&Key::Int(7)), Some(&obj::Int(4)));
+
+        // overwriting a shifted key keeps its place
+        map.insert(Key::Str("b".to_string()), obj::Int(30));
+        assert_eq!(keys(&map), [Key::Str("a".to_string()), Key::Str("b".to_string()), Key::Int(7)]);
+        assert_eq!(map.get(&Key::Str("b".to_string())), Some(&obj::Int(30)));
+    }
+
+    #[test]
+    fn remove_does_not_reuse_the_next_index() {
+        let mut map = OrderedMap::from_list(vec![obj::Int(0), obj::Int(1), obj::Int(2)]);
+        map.remove(&Key::Int(2));
+        map.remove(&Key::Int(0));
+        assert_eq!(map.push(obj::Int(3)), Ok(Key::Int(3)));
+        assert_eq!(keys(&map), [Key::Int(1), Key::Int(3)]);
+        assert_eq!(map.get(&Key::Int(3)), Some(&obj::Int(3)));
+    }
+
+    #[test]
+    fn push_fails_after_the_largest_int_key() {
+        let mut map = OrderedMap::new();
+        map.insert(Key::Int(i64::MAX), obj::Null);
+        assert!(map.push(obj::Null).is_err());
+    }
+}
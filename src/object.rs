@@ -1,22 +1,599 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::fmt;
-use crate::ast::Expr;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+use crate::ast::{Expr, Stmt};
+use crate::decimal::Decimal;
+use crate::environment::Environment;
+use crate::interpreter::Interpreter;
+use crate::map::OrderedMap;
+use crate::token::Token;
 
 
+#[allow(non_camel_case_types)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum obj {
     Str(String),
-    Num(f64),
+    Int(i64),
+    Float(f64),
     Bool(bool),
     Null,
-    Identifier(String)
+    Identifier(String),
+    Array(Vec<obj>),
+    /// an array with non-sequential or string keys
+    Map(OrderedMap),
+    Function(Rc<Function>),
+    Builtin(Builtin),
+    Class(Rc<Class>),
+    Instance(Rc<RefCell<Instance>>),
+    Interface(Rc<Interface>),
+    Trait(Rc<Trait>)
+}
+
+impl obj {
+    /// The name of this value's type, as used in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            obj::Str(_) => "string",
+            obj::Int(_) => "int",
+            obj::Float(_) => "float",
+            obj::Bool(_) => "bool",
+            obj::Null => "null",
+            obj::Identifier(_) => "identifier",
+            obj::Array(_) | obj::Map(_) => "array",
+            obj::Function(_) | obj::Builtin(_) => "function",
+            obj::Class(_) => "class",
+            obj::Instance(_) => "object",
+            obj::Interface(_) => "interface",
+            obj::Trait(_) => "trait"
+        }
+    }
+
+    /// PHP's conversion to bool: false, 0, "", "0", null and empty arrays are false.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            obj::Bool(b) => *b,
+            obj::Int(n) => *n != 0,
+            obj::Float(n) => *n != 0.0,
+            obj::Str(s) => !s.is_empty() && s != "0",
+            obj::Null => false,
+            obj::Array(items) => !items.is_empty(),
+            obj::Map(map) => map.len() > 0,
+            _ => true
+        }
+    }
+
+    /// PHP's loose comparison `==`, following PHP 8's comparison table.
+    pub fn loose_eq(&self, other: &obj) -> bool {
+        self.loose_eq_in(other, &mut Vec::new())
+    }
+
+    fn loose_eq_in(&self, other: &obj, comparing: &mut Comparing) -> bool {
+        match (self, other) {
+            (obj::Null, obj::Null) => true,
+            (obj::Bool(_), _) | (_, obj::Bool(_)) => self.is_truthy() == other.is_truthy(),
+            (obj::Null, obj::Str(s)) | (obj::Str(s), obj::Null) => s.is_empty(),
+            (obj::Null, _) | (_, obj::Null) => !self.is_truthy() && !other.is_truthy(),
+            (obj::Int(n1), obj::Int(n2)) => n1 == n2,
+            (obj::Int(_) | obj::Float(_), obj::Int(_) | obj::Float(_)) => self.to_float() == other.to_float(),
+            (number @ (obj::Int(_) | obj::Float(_)), obj::Str(s)) | (obj::Str(s), number @ (obj::Int(_) | obj::Float(_))) => {
+                match numeric_string(s) {
+                    Some(value) => number.loose_eq_in(&value, comparing),
+                    None => number.to_string() == *s
+                }
+            }
+            (obj::Str(s1), obj::Str(s2)) => compare_strings(s1, s2) == Ordering::Equal,
+            (obj::Array(_) | obj::Map(_), obj::Array(_) | obj::Map(_)) => {
+                let (left, right) = (self.to_map(), other.to_map());
+                left.len() == right.len()
+                    && left.iter().all(|(key, value)| {
+                        right.get(key).is_some_and(|other| value.loose_eq_in(other, comparing))
+                    })
+            }
+            (obj::Instance(i1), obj::Instance(i2)) => {
+                let pair = (Rc::as_ptr(i1), Rc::as_ptr(i2));
+                if Rc::ptr_eq(i1, i2) || comparing.contains(&pair) {
+                    return true;
+                }
+                comparing.push(pair);
+                let (i1, i2) = (i1.borrow(), i2.borrow());
+                let equal = Rc::ptr_eq(&i1.class, &i2.class)
+                    && i1.fields.len() == i2.fields.len()
+                    && i1.fields.iter().all(|(name, value)| {
+                        i2.fields.get(name).is_some_and(|other| value.loose_eq_in(other, comparing))
+                    });
+                comparing.pop();
+                equal
+            }
+            _ => self == other
+        }
+    }
+
+    /// PHP's strict comparison `===`: same type and value, and for objects the same instance.
+    pub fn strict_eq(&self, other: &obj) -> bool {
+        match (self, other) {
+            (obj::Instance(i1), obj::Instance(i2)) => Rc::ptr_eq(i1, i2),
+            (obj::Array(_) | obj::Map(_), obj::Array(_) | obj::Map(_)) => {
+                let (left, right) = (self.to_map(), other.to_map());
+                left.len() == right.len()
+                    && left.iter().zip(right.iter()).all(|((k1, v1), (k2, v2))| {
+                        k1 == k2 && v1.strict_eq(v2)
+                    })
+            }
+            _ => self == other
+        }
+    }
+
+    /// PHP 8's ordering of two values, used by `<`, `>`, `<=`, `>=` and `<=>`.
+    /// None when the values can't be ordered, like arrays with different keys,
+    /// objects of different classes or NAN.
+    pub fn compare(&self, other: &obj) -> Option<Ordering> {
+        self.compare_in(other, &mut Vec::new())
+    }
+
+    fn compare_in(&self, other: &obj, comparing: &mut Comparing) -> Option<Ordering> {
+        match (self, other) {
+            (obj::Null, obj::Str(s)) => Some(compare_strings("", s)),
+            (obj::Str(s), obj::Null) => Some(compare_strings(s, "")),
+            (obj::Bool(_) | obj::Null, _) | (_, obj::Bool(_) | obj::Null) => {
+                Some(self.is_truthy().cmp(&other.is_truthy()))
+            }
+            (obj::Int(n1), obj::Int(n2)) => Some(n1.cmp(n2)),
+            (obj::Int(_) | obj::Float(_), obj::Int(_) | obj::Float(_)) => self.to_float().partial_cmp(&other.to_float()),
+            (number @ (obj::Int(_) | obj::Float(_)), obj::Str(s)) => match numeric_string(s) {
+                Some(value) => number.compare_in(&value, comparing),
+                None => Some(number.to_string().as_str().cmp(s))
+            },
+            (obj::Str(_), obj::Int(_) | obj::Float(_)) => other.compare_in(self, comparing).map(Ordering::reverse),
+            (obj::Str(s1), obj::Str(s2)) => Some(compare_strings(s1, s2)),
+            (obj::Array(_) | obj::Map(_), obj::Array(_) | obj::Map(_)) => {
+                let (left, right) = (self.to_map(), other.to_map());
+                let pairs = left.iter().map(|(key, value)| (value, right.get(key)));
+                compare_fields(left.len(), right.len(), pairs, comparing)
+            }
+            (obj::Instance(i1), obj::Instance(i2)) => {
+                let pair = (Rc::as_ptr(i1), Rc::as_ptr(i2));
+                if Rc::ptr_eq(i1, i2) || comparing.contains(&pair) {
+                    return Some(Ordering::Equal);
+                }
+                let (i1, i2) = (i1.borrow(), i2.borrow());
+                if !Rc::ptr_eq(&i1.class, &i2.class) {
+                    return None;
+                }
+                comparing.push(pair);
+                let pairs = i1.fields.iter().map(|(name, value)| (value, i2.fields.get(name)));
+                let ordering = compare_fields(i1.fields.len(), i2.fields.len(), pairs, comparing);
+                comparing.pop();
+                ordering
+            }
+            (obj::Array(_) | obj::Map(_) | obj::Instance(_), _) => Some(Ordering::Greater),
+            (_, obj::Array(_) | obj::Map(_) | obj::Instance(_)) => Some(Ordering::Less),
+            _ if self == other => Some(Ordering::Equal),
+            _ => None
+        }
+    }
+
+    /// The value as a number for arithmetic: null is 0, booleans are 0 or 1 and
+    /// strings give their leading number, so "5 apples" is 5. None for the values
+    /// arithmetic isn't defined on, like arrays and strings that don't start with a number.
+    pub fn to_number(&self) -> Option<obj> {
+        match self {
+            obj::Int(_) | obj::Float(_) => Some(self.clone()),
+            obj::Null => Some(obj::Int(0)),
+            obj::Bool(b) => Some(obj::Int(i64::from(*b))),
+            obj::Str(s) => leading_number(s),
+            _ => None
+        }
+    }
+
+    /// PHP's conversion to string, as done by `.`: true is "1", false and null are "",
+    /// and arrays are "Array". None for values that have no string form, like objects.
+    pub fn to_php_string(&self) -> Option<String> {
+        match self {
+            obj::Str(s) => Some(s.clone()),
+            obj::Int(_) | obj::Float(_) => Some(self.to_string()),
+            obj::Bool(true) => Some("1".to_string()),
+            obj::Bool(false) | obj::Null => Some(String::new()),
+            obj::Array(_) | obj::Map(_) => Some("Array".to_string()),
+            _ => None
+        }
+    }
+
+    /// `left + right` on arrays: every element of `left`, then those of `right`
+    /// whose keys aren't in `left`.
+    pub fn union(&self, other: &obj) -> obj {
+        let mut union = self.to_map();
+        for (key, value) in other.to_map().iter() {
+            if union.get(key).is_none() {
+                union.insert(key.clone(), value.clone());
+            }
+        }
+        obj::Map(union)
+    }
+
+    /// A number as an integer; floats are truncated towards zero.
+    pub fn to_int(&self) -> i64 {
+        match self {
+            obj::Int(n) => *n,
+            obj::Float(n) => *n as i64,
+            _ => 0
+        }
+    }
+
+    /// A number as a float.
+    pub fn to_float(&self) -> f64 {
+        match self {
+            obj::Int(n) => *n as f64,
+            obj::Float(n) => *n,
+            _ => 0.0
+        }
+    }
+
+    /// A copy of an array value as an ordered map; other values give an empty map.
+    fn to_map(&self) -> OrderedMap {
+        match self {
+            obj::Array(items) => OrderedMap::from_list(items.clone()),
+            obj::Map(map) => map.clone(),
+            _ => OrderedMap::new()
+        }
+    }
+}
+
+/// The number held by a PHP numeric string such as "42", " 1.5" or "1e3".
+pub fn numeric_value(s: &str) -> Option<f64> {
+    let trimmed = s.trim_matches(|c: char| c.is_ascii_whitespace());
+    let is_numeric_char = |c: char| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-');
+    if trimmed.is_empty() || !trimmed.chars().all(is_numeric_char) {
+        return None;
+    }
+    trimmed.parse::<f64>().ok()
+}
+
+/// The pairs of instances whose fields are being compared. A pair met again
+/// while comparing their fields is taken to be equal, so that comparing objects
+/// that refer to themselves ends.
+type Comparing = Vec<(*const RefCell<Instance>, *const RefCell<Instance>)>;
+
+/// Orders two arrays or objects as PHP does: the one with fewer elements first,
+/// then element by element in the order of the left one; None when the right
+/// one lacks a key of the left one.
+fn compare_fields<'a>(
+    len1: usize,
+    len2: usize,
+    mut pairs: impl Iterator<Item = (&'a obj, Option<&'a obj>)>,
+    comparing: &mut Comparing
+) -> Option<Ordering> {
+    if len1 != len2 {
+        return Some(len1.cmp(&len2));
+    }
+    pairs.try_fold(Ordering::Equal, |ordering, (value, other)| match ordering {
+        Ordering::Equal => value.compare_in(other?, comparing),
+        _ => Some(ordering)
+    })
+}
+
+/// The number a string starts with, after any leading whitespace: an integer,
+/// or a float when it has a fraction or an exponent or doesn't fit in 64 bits.
+fn leading_number(s: &str) -> Option<obj> {
+    let s = s.trim_start_matches(|c: char| c.is_ascii_whitespace());
+    let bytes = s.as_bytes();
+    let count_digits = |from: usize| bytes[from..].iter().take_while(|b| b.is_ascii_digit()).count();
+    let sign = usize::from(matches!(bytes.first(), Some(b'+' | b'-')));
+    let integer = count_digits(sign);
+    let mut end = sign + integer;
+    let mut is_float = false;
+    if bytes.get(end) == Some(&b'.') {
+        let fraction = count_digits(end + 1);
+        // "5." and ".5" are numbers, a lone "." isn't
+        if integer + fraction > 0 {
+            end += 1 + fraction;
+            is_float = true;
+        }
+    }
+    if integer == 0 && !is_float {
+        return None;
+    }
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        let exponent_sign = usize::from(matches!(bytes.get(end + 1), Some(b'+' | b'-')));
+        let exponent = count_digits(end + 1 + exponent_sign);
+        if exponent > 0 {
+            end += 1 + exponent_sign + exponent;
+            is_float = true;
+        }
+    }
+    let number = &s[..end];
+    match number.parse::<i64>() {
+        Ok(n) if !is_float => Some(obj::Int(n)),
+        _ => number.parse::<f64>().ok().map(obj::Float)
+    }
+}
+
+/// The number a PHP numeric string holds: an int when it is an integer that
+/// fits in 64 bits, so comparing it with an int is exact, and a float otherwise.
+pub fn numeric_string(s: &str) -> Option<obj> {
+    numeric_value(s)?;
+    leading_number(s)
+}
+
+/// PHP's `++` on a string that isn't numeric: the last letter or digit steps on,
+/// carrying like an odometer, so "a9" becomes "b0" and "Zz" becomes "AAa". A string
+/// that doesn't end in a letter or digit is left as it is.
+pub fn increment_string(s: &str) -> String {
+    let mut chars: Vec<char> = s.chars().collect();
+    for i in (0..chars.len()).rev() {
+        let (next, carry) = match chars[i] {
+            'z' => ('a', true),
+            'Z' => ('A', true),
+            '9' => ('0', true),
+            c if c.is_ascii_alphanumeric() => ((c as u8 + 1) as char, false),
+            _ => break
+        };
+        chars[i] = next;
+        if !carry {
+            break;
+        }
+        if i == 0 {
+            chars.insert(0, if next == '0' { '1' } else { next });
+        }
+    }
+    chars.into_iter().collect()
+}
+
+/// Compares two strings the way PHP does: as numbers when both are numeric,
+/// exactly for decimal strings of any length, and byte by byte otherwise.
+pub fn compare_strings(s1: &str, s2: &str) -> Ordering {
+    if let (Some(d1), Some(d2)) = (Decimal::parse(s1), Decimal::parse(s2)) {
+        return d1.cmp(&d2);
+    }
+    match (numeric_value(s1), numeric_value(s2)) {
+        (Some(n1), Some(n2)) => n1.partial_cmp(&n2).unwrap_or(Ordering::Less),
+        _ => s1.cmp(s2)
+    }
+}
+
+/// A float the way PHP's `echo` prints it: rounded to 14 significant digits,
+/// without trailing zeros, and in exponent form (`1.0E+25`) when very large or small.
+pub fn format_float(n: f64) -> String {
+    if n.is_nan() {
+        return "NAN".to_string();
+    }
+    if n.is_infinite() {
+        return if n > 0.0 { "INF" } else { "-INF" }.to_string();
+    }
+    if n == 0.0 {
+        return if n.is_sign_negative() { "-0" } else { "0" }.to_string();
+    }
+    let scientific = format!("{:.13e}", n.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let digits = mantissa.replace('.', "");
+    let digits = digits.trim_end_matches('0');
+    let sign = if n < 0.0 { "-" } else { "" };
+    if !(-4..14).contains(&exponent) {
+        let fraction = if digits.len() > 1 { &digits[1..] } else { "0" };
+        let exponent_sign = if exponent < 0 { '-' } else { '+' };
+        return format!("{sign}{}.{fraction}E{exponent_sign}{}", &digits[..1], exponent.abs());
+    }
+    if exponent < 0 {
+        return format!("{sign}0.{}{digits}", "0".repeat((-exponent - 1) as usize));
+    }
+    let point = exponent as usize + 1;
+    if digits.len() <= point {
+        format!("{sign}{digits}{}", "0".repeat(point - digits.len()))
+    } else {
+        format!("{sign}{}.{}", &digits[..point], &digits[point..])
+    }
+}
+
+/// A function implemented in Rust, see `builtins.rs`.
+#[derive(Debug, Clone)]
+pub struct Builtin {
+    pub name: &'static str,
+    /// how many arguments the function accepts
+    pub arity: RangeInclusive<usize>,
+    pub function: fn(&mut Interpreter, Vec<obj>) -> Result<obj, BuiltinError>
+}
+
+/// Why a builtin function failed.
+#[derive(Debug)]
+pub enum BuiltinError {
+    /// an error that stops the script
+    Error(String),
+    /// an exception of the named prelude class, which the script can catch
+    Throw(&'static str, String)
+}
+
+impl From<String> for BuiltinError {
+    fn from(msg: String) -> BuiltinError {
+        BuiltinError::Error(msg)
+    }
+}
+
+impl PartialEq for Builtin {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+/// A user-defined function, either declared with `fn name(params) { ... }`
+/// or created by a closure expression. `closure` is the scope the function
+/// was created in, so captured variables are shared by reference, except that
+/// an arrow function's `closure` is a copy made when it was created.
+pub struct Function {
+    pub name: String,
+    /// each parameter with its default value, if it has one
+    pub params: Vec<(Token, Option<Expr>)>,
+    pub body: Rc<Vec<Stmt>>,
+    pub closure: Rc<RefCell<Environment>>
+}
+
+impl Function {
+    /// Returns a copy of this method whose scope has `this` bound to `instance`.
+    pub fn bind(&self, instance: obj) -> Function {
+        let mut environment = Environment::with_enclosing(Rc::clone(&self.closure));
+        environment.define("this".to_string(), instance);
+        Function {
+            name: self.name.clone(),
+            params: self.params.clone(),
+            body: Rc::clone(&self.body),
+            closure: Rc::new(RefCell::new(environment))
+        }
+    }
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<fn {}>", self.name)
+    }
+}
+
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// A class created by a `class Name { ... }` declaration.
+#[derive(Debug)]
+pub struct Class {
+    pub name: String,
+    pub is_abstract: bool,
+    pub superclass: Option<Rc<Class>>,
+    pub interfaces: Vec<Rc<Interface>>,
+    /// declared properties with their default values, inherited ones first
+    pub properties: Vec<(String, obj)>,
+    pub methods: HashMap<String, Rc<Function>>,
+    /// methods an abstract class leaves for its subclasses to implement
+    pub abstract_methods: Vec<String>
+}
+
+impl Class {
+    /// Looks a method up on this class and then on each of its superclasses.
+    pub fn find_method(&self, name: &str) -> Option<Rc<Function>> {
+        match self.methods.get(name) {
+            Some(method) => Some(Rc::clone(method)),
+            None => self.superclass.as_ref()?.find_method(name)
+        }
+    }
+
+    /// True when `other` is this class or one of its superclasses.
+    pub fn is_subclass_of(&self, other: &Class) -> bool {
+        if std::ptr::eq(self, other) {
+            return true;
+        }
+        match &self.superclass {
+            Some(superclass) => superclass.is_subclass_of(other),
+            None => false
+        }
+    }
+
+    /// True when this class or one of its superclasses implements `interface`.
+    pub fn implements(&self, interface: &Interface) -> bool {
+        self.interfaces.iter().any(|own| own.extends(interface))
+            || self.superclass.as_ref().is_some_and(|superclass| superclass.implements(interface))
+    }
+}
+
+impl PartialEq for Class {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// An interface created by an `interface Name { fn method(); }` declaration.
+#[derive(Debug)]
+pub struct Interface {
+    pub name: String,
+    pub parents: Vec<Rc<Interface>>,
+    pub methods: Vec<String>
+}
+
+impl Interface {
+    /// True when `other` is this interface or one it extends.
+    pub fn extends(&self, other: &Interface) -> bool {
+        std::ptr::eq(self, other) || self.parents.iter().any(|parent| parent.extends(other))
+    }
+
+    /// The methods declared by this interface and every interface it extends.
+    pub fn all_methods(&self) -> Vec<String> {
+        let mut methods = self.methods.clone();
+        for parent in &self.parents {
+            methods.extend(parent.all_methods());
+        }
+        methods
+    }
+}
+
+impl PartialEq for Interface {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// A trait keeps its members as syntax; they are turned into properties and
+/// methods of each class that uses the trait when that class is declared.
+#[derive(Debug, PartialEq)]
+pub struct Trait {
+    pub name: String,
+    pub properties: Vec<(Token, Expr)>,
+    pub methods: Vec<Stmt>,
+    pub abstract_methods: Vec<Token>
+}
+
+/// An object created with `new`. Instances are shared by reference.
+#[derive(Debug, PartialEq)]
+pub struct Instance {
+    pub class: Rc<Class>,
+    pub fields: HashMap<String, obj>
+}
+
+impl Instance {
+    pub fn new(class: Rc<Class>) -> Instance {
+        let fields = class.properties.iter().cloned().collect();
+        Instance {
+            class,
+            fields
+        }
+    }
 }
 
 impl fmt::Display for obj {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            obj::Num(x) => write!(f, "{x}"),
+            obj::Int(n) => write!(f, "{n}"),
+            obj::Float(n) => write!(f, "{}", format_float(*n)),
             obj::Str(x) => write!(f, "{x}"),
-            _ => panic!("should not print this")
+            obj::Bool(b) => write!(f, "{b}"),
+            obj::Null => write!(f, "Null"),
+            obj::Identifier(i) => write!(f, "{i}"),
+            obj::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            }
+            obj::Map(map) => {
+                write!(f, "[")?;
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{key} => {value}")?;
+                }
+                write!(f, "]")
+            }
+            obj::Function(function) => write!(f, "<fn {}>", function.name),
+            obj::Builtin(builtin) => write!(f, "<fn {}>", builtin.name),
+            obj::Class(class) => write!(f, "<class {}>", class.name),
+            obj::Instance(instance) => write!(f, "<{} instance>", instance.borrow().class.name),
+            obj::Interface(interface) => write!(f, "<interface {}>", interface.name),
+            obj::Trait(t) => write!(f, "<trait {}>", t.name)
         }
     }
 }
@@ -1,344 +1,1097 @@
-use std::process::id;
 use crate::Token;
-use crate::ast::{Expr, Stmt};
-use crate::ast::Expr::{Assign, Binary, Grouping, Literal, Unary};
-/*use crate::ast::Stmt::Block;*/
+use crate::ast::{Expr, Stmt, StmtKind};
+use crate::ast::ExprKind::{Array, Assign, Binary, Call, Closure, Get, Grouping, Increment, Index, Literal, Match, New, Parent, Unary};
 use crate::error::ScrapError;
-use crate::error::ScrapError::{InvalidSyntax, ParserError};
+use crate::error::ErrorType::{InvalidSyntax, ParserError};
 use crate::object::obj;
+use crate::span::Span;
 use crate::tokentype::TType;
-use crate::tokentype::TType::{And, Bang, BangEqual, Comma, Echo, Else, Eof, Equal, EqualEqual, False, Fn, Greater, GreaterEqual, Identifier, If, LeftCurly, LeftParen, Less, LessEqual, Minus, MinusEqual, Null, Number, Or, Plus, PlusEqual, RightBracket, RightCurly, RightParen, Semicolon, Slash, Star, String_tok, True, Var, While};
+use crate::tokentype::TType::{Abstract, Ampersand, AmpersandEqual, And, Arrow, As, Bang, BangEqual, BangEqualEqual, Break, Caret, CaretEqual, Case, Catch, Class, Colon, Comma, Continue, Default, DotEqual, Dot, DoubleArrow, DoubleColon, Echo, Else, ElseIf, Eof, Equal, EqualEqual, EqualEqualEqual, Extends, Finally, Fn, For, Foreach, Greater, GreaterEqual, GreaterGreater, GreaterGreaterEqual, Identifier, If, Implements, Instanceof, Interface, LeftBracket, LeftCurly, LeftParen, Less, LessEqual, LessLess, LessLessEqual, Minus, MinusEqual, MinusMinus, Number, Or, Percent, PercentEqual, Pipe, PipeEqual, Plus, PlusEqual, PlusPlus, QuestionQuestion, QuestionQuestionEqual, Return, RightBracket, RightCurly, RightParen, Semicolon, Slash, SlashEqual, Spaceship, Star, StarEqual, StarStar, StarStarEqual, Switch, Throw, Tilde, Trait, Try, Unset, Use, Var, While};
+
+/// The members of a class or trait body.
+#[derive(Default)]
+struct ClassBody {
+    traits: Vec<Token>,
+    properties: Vec<(Token, Expr)>,
+    methods: Vec<Stmt>,
+    abstract_methods: Vec<Token>
+}
 
 pub struct Parser {
     pub tokens: Vec<Token>,
-    current_token: Option<Token>,
     index: usize,
-    pub expressions: Vec<Expr>,
-    pub statements: Vec<Stmt>
+    /// how many loops enclose the statement being parsed, within the current function
+    loop_depth: usize,
+    pub statements: Vec<Stmt>,
+    /// syntax errors found so far; the parser skips past each one and goes on
+    errors: Vec<ScrapError>
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Parser {
-        let current_token = tokens.get(0).cloned();
         Parser {
             tokens,
-            current_token,
             index: 0,
-            expressions: Vec::new(),
+            loop_depth: 0,
             statements: Vec::new(),
+            errors: Vec::new()
         }
     }
-    pub fn parse(&mut self) {
+    /// Parses the whole file into `statements`. Statements with syntax errors are
+    /// left out, so after an error `statements` holds what could be parsed.
+    pub fn parse(&mut self) -> Result<(), Vec<ScrapError>> {
         while !self.is_at_end() {
-            let expr = self.declaration();
-            self.statements.push(expr.clone()); //remove clone when removing debug println
-            let semicolons: Vec<TType> = vec![Semicolon,LeftCurly,RightCurly];
-            if semicolons.contains(&self.peek().unwrap().ttype) {
-                self.advance();
-            } else {
-                /*println!("prev token: {:?}", self.previous());
-                println!("current token: {:?}", self.peek());*/
-                ScrapError::error(
-                    InvalidSyntax,
-                    "Missing semicolon",
-                    self.peek().unwrap().line,
-                    file!()
-                );
+            // a stray ';' (e.g. after a closing '}') is an empty statement
+            if self.match_next(&[Semicolon]) {
+                continue;
+            }
+            let start = self.index;
+            match self.declaration() {
+                Ok(stmt) => self.statements.push(stmt),
+                Err(error) => {
+                    self.errors.push(error);
+                    self.loop_depth = 0;
+                    self.synchronize(start);
+                }
+            }
+        }
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+    /// Skips the rest of a statement with a syntax error: up to and including the next ';',
+    /// or up to a '}', a switch label or a keyword that starts a statement. A statement that failed on
+    /// its first token loses at least that token, so parsing always moves on.
+    fn synchronize(&mut self, start: usize) {
+        if self.index == start {
+            self.advance();
+        }
+        while !self.is_at_end() {
+            if self.previous().unwrap().ttype == Semicolon {
+                return;
+            }
+            match self.peek().unwrap().ttype {
+                RightCurly | Var | Class | Abstract | Interface | Trait | Fn | Echo | If | While
+                | Switch | For | Foreach | Return | Break | Continue | Unset | Throw | Try | Case
+                | Default => return,
+                _ => {
+                    self.advance();
+                }
             }
-            // println!("stmt: {:#?}", &expr)
         }
-
     }
     //parsing functions
-    fn declaration(&mut self) -> Stmt {
+    fn declaration(&mut self) -> Result<Stmt, ScrapError> {
         if self.match_next(&[Var]) {
-            return self.variable_declaration();
+            self.variable_declaration()
+        } else if self.match_next(&[Class]) {
+            self.class_declaration(false)
+        } else if self.match_next(&[Abstract]) {
+            self.consume(Class, "expected 'class' after 'abstract'")?;
+            self.class_declaration(true)
+        } else if self.match_next(&[Interface]) {
+            self.interface_declaration()
+        } else if self.match_next(&[Trait]) {
+            self.trait_declaration()
+        } else if self.check(&Fn) && self.check_next(&Identifier) {
+            self.advance();
+            self.function_declaration()
         } else {
-            return self.statement();
+            self.statement()
         }
     }
-    fn statement(&mut self) -> Stmt {
-        // println!("{} returned statement", self.current_token.clone().unwrap().line);
+    fn statement(&mut self) -> Result<Stmt, ScrapError> {
+        let start = self.index;
         if self.match_next(&[Echo]) {
-            return self.print_stmt()
+            self.print_stmt()
         } else if self.match_next(&[If]) {
-            // println!("reached if in statement");
-            let test = self.if_stmt();
-            // println!("{:#?}", test);
-            return test;
+            self.if_stmt()
         } else if self.match_next(&[While]) {
-            return self.while_stmt();
+            self.while_stmt()
+        } else if self.match_next(&[Switch]) {
+            self.switch_stmt()
+        } else if self.match_next(&[For]) {
+            self.for_stmt()
+        } else if self.match_next(&[Foreach]) {
+            self.foreach_stmt()
+        } else if self.match_next(&[Return]) {
+            self.return_stmt()
+        } else if self.match_next(&[Break, Continue]) {
+            self.break_stmt()
+        } else if self.match_next(&[Unset]) {
+            self.unset_stmt()
+        } else if self.match_next(&[Throw]) {
+            self.throw_stmt()
+        } else if self.match_next(&[Try]) {
+            self.try_stmt()
+        } else if self.match_next(&[LeftCurly]) {
+            Ok(Stmt::new(StmtKind::Block(self.block()?), self.span_from(start)))
+        } else {
+            self.expression_stmt()
+        }
+    }
+    fn variable_declaration(&mut self) -> Result<Stmt, ScrapError> {
+        let start = self.index - 1;
+        let identifier = self.consume(Identifier, "expected variable name after 'var'")?.literal;
+        let value = if self.match_next(&[Equal]) {
+            self.expression()?
+        } else {
+            Expr::new(Literal(obj::Null), self.span_from(start))
+        };
+        self.consume(Semicolon, "Missing semicolon")?;
+        Ok(Stmt::new(StmtKind::VariableAssign {
+            identifier,
+            value: Box::new(value)
+        }, self.span_from(start)))
+    }
+    fn function_declaration(&mut self) -> Result<Stmt, ScrapError> {
+        let start = self.index - 1;
+        let name = self.consume(Identifier, "expected function name after 'fn'")?;
+        self.consume(LeftParen, "expected '(' after function name")?;
+        let params = self.parameters()?;
+        self.consume(LeftCurly, "expected '{' before function body")?;
+        let body = self.function_body()?;
+        Ok(Stmt::new(StmtKind::Function {
+            name,
+            params,
+            body
+        }, self.span_from(start)))
+    }
+    fn class_declaration(&mut self, is_abstract: bool) -> Result<Stmt, ScrapError> {
+        let start = self.index - 1;
+        let name = self.consume(Identifier, "expected class name after 'class'")?;
+        let superclass = if self.match_next(&[Extends]) {
+            Some(self.consume(Identifier, "expected superclass name after 'extends'")?)
+        } else {
+            None
+        };
+        let interfaces = if self.match_next(&[Implements]) {
+            self.identifier_list("expected interface name after 'implements'")?
+        } else {
+            Vec::new()
+        };
+        self.consume(LeftCurly, "expected '{' before class body")?;
+        let body = self.class_body()?;
+        Ok(Stmt::new(StmtKind::Class {
+            name,
+            is_abstract,
+            superclass,
+            interfaces,
+            traits: body.traits,
+            properties: body.properties,
+            methods: body.methods,
+            abstract_methods: body.abstract_methods
+        }, self.span_from(start)))
+    }
+    fn interface_declaration(&mut self) -> Result<Stmt, ScrapError> {
+        let start = self.index - 1;
+        let name = self.consume(Identifier, "expected interface name after 'interface'")?;
+        let parents = if self.match_next(&[Extends]) {
+            self.identifier_list("expected interface name after 'extends'")?
         } else {
-            return Stmt::Expression(Box::new(self.expression()));
-        }
-    }
-    fn variable_declaration(&mut self) -> Stmt {
-        // println!("{:?}", self.current_token);
-        let operations = [Equal, Plus, Minus, Star, Slash];
-        let mut val = Expr::Literal(obj::Null);
-        let mut identifier= String::new();
-        let mut statement = Stmt::Expression(Box::new(Literal(obj::Null)));
-        if self.match_next(&[Identifier]) || self.check(&Identifier) {
-            // println!("{:?}", self.current_token);
-            if self.previous().unwrap().ttype == Identifier {
-                identifier = self.previous().unwrap().literal.clone();
+            Vec::new()
+        };
+        self.consume(LeftCurly, "expected '{' before interface body")?;
+        let mut methods = Vec::new();
+        while !self.check(&RightCurly) && !self.is_at_end() {
+            self.consume(Fn, "expected 'fn' in interface body")?;
+            methods.push(self.method_signature()?);
+        }
+        self.consume(RightCurly, "expected '}' after interface body")?;
+        Ok(Stmt::new(StmtKind::Interface {
+            name,
+            parents,
+            methods
+        }, self.span_from(start)))
+    }
+    fn trait_declaration(&mut self) -> Result<Stmt, ScrapError> {
+        let start = self.index - 1;
+        let name = self.consume(Identifier, "expected trait name after 'trait'")?;
+        self.consume(LeftCurly, "expected '{' before trait body")?;
+        let body = self.class_body()?;
+        if let Some(used) = body.traits.first() {
+            return Err(ScrapError::error(
+                ParserError,
+                "traits can't use other traits",
+                used.span
+            ));
+        }
+        Ok(Stmt::new(StmtKind::Trait {
+            name,
+            properties: body.properties,
+            methods: body.methods,
+            abstract_methods: body.abstract_methods
+        }, self.span_from(start)))
+    }
+    /// Parses the members of a class or trait up to and including the closing '}'.
+    fn class_body(&mut self) -> Result<ClassBody, ScrapError> {
+        let mut body = ClassBody::default();
+        while !self.check(&RightCurly) && !self.is_at_end() {
+            let start = self.index;
+            if let Err(error) = self.class_member(&mut body) {
+                self.errors.push(error);
+                self.synchronize(start);
             }
-            if self.match_next(&[Equal]) {
-                val = self.expression();
-                statement =  Stmt::VariableAssign {
-                    identifier,
-                    value: Box::new(val)
-                };
-            } else if self.previous().unwrap().ttype != Var {
-                let identifier = self.expression();
-                statement = Stmt::VariableCall {
-                    identifier: Box::new(identifier)
+        }
+        self.consume(RightCurly, "expected '}' after class body")?;
+        Ok(body)
+    }
+    fn class_member(&mut self, body: &mut ClassBody) -> Result<(), ScrapError> {
+        let start = self.index;
+        if self.match_next(&[Var]) {
+            let property = self.consume(Identifier, "expected property name after 'var'")?;
+            let value = if self.match_next(&[Equal]) {
+                self.expression()?
+            } else {
+                Expr::new(Literal(obj::Null), self.span_from(start))
+            };
+            self.consume(Semicolon, "Missing semicolon")?;
+            body.properties.push((property, value));
+        } else if self.match_next(&[Fn]) {
+            body.methods.push(self.function_declaration()?);
+        } else if self.match_next(&[Abstract]) {
+            self.consume(Fn, "expected 'fn' after 'abstract'")?;
+            body.abstract_methods.push(self.method_signature()?);
+        } else if self.match_next(&[Use]) {
+            let traits = self.identifier_list("expected trait name after 'use'")?;
+            body.traits.extend(traits);
+            self.consume(Semicolon, "Missing semicolon")?;
+        } else {
+            let span = self.peek().unwrap().span;
+            return Err(ScrapError::error(
+                ParserError,
+                "expected 'var', 'fn', 'abstract' or 'use' in class body",
+                span
+            ));
+        }
+        Ok(())
+    }
+    /// Parses `name(params);`, a method declared without a body, and returns its name.
+    fn method_signature(&mut self) -> Result<Token, ScrapError> {
+        let name = self.consume(Identifier, "expected method name after 'fn'")?;
+        self.consume(LeftParen, "expected '(' after method name")?;
+        self.parameters()?;
+        self.consume(Semicolon, "Missing semicolon")?;
+        Ok(name)
+    }
+    fn identifier_list(&mut self, msg: &str) -> Result<Vec<Token>, ScrapError> {
+        let mut names = vec![self.consume(Identifier, msg)?];
+        while self.match_next(&[Comma]) {
+            names.push(self.consume(Identifier, msg)?);
+        }
+        Ok(names)
+    }
+    fn parameters(&mut self) -> Result<Vec<(Token, Option<Expr>)>, ScrapError> {
+        let mut params = Vec::new();
+        if !self.check(&RightParen) {
+            loop {
+                let name = self.consume(Identifier, "expected parameter name")?;
+                let default = if self.match_next(&[Equal]) {
+                    Some(self.expression()?)
+                } else {
+                    None
                 };
+                params.push((name, default));
+                if !self.match_next(&[Comma]) {
+                    break;
+                }
             }
         }
-        statement
+        self.consume(RightParen, "expected ')' after parameters")?;
+        Ok(params)
     }
-    fn if_stmt(&mut self) -> Stmt {
-        let expr = Box::new(self.expression());
-        let mut block = self.declaration();
-        let mut block2 = self.declaration();
-        if self.match_next(&[LeftCurly]) {
-            let mut stmts = Vec::new();
-            while !self.check(&RightCurly) {
-                let expr = self.declaration();
-
-                stmts.push(expr);
-                self.advance();
+    fn if_stmt(&mut self) -> Result<Stmt, ScrapError> {
+        let start = self.index - 1;
+        let mut branches = Vec::new();
+        let mut elseblock = None;
+        loop {
+            let expr = self.condition()?;
+            let block = self.branch_body()?;
+            branches.push((expr, block));
+            if self.match_next(&[ElseIf]) {
+                continue;
             }
-
-            block = Stmt::Block(stmts);
-        }
-        self.advance();
-        if self.match_next(&[Else]) {
-            if self.match_next(&[LeftCurly]) {
-                let mut stmts = Vec::new();
-                while !self.check(&RightCurly) {
-                    let expr = self.declaration();
-
-                    stmts.push(expr);
-                    self.advance();
+            if self.match_next(&[Else]) {
+                if self.match_next(&[If]) {
+                    continue;
                 }
-                block2 = Stmt::Block(stmts);
-
-            }
-            Stmt::Ifstmt {
-                expr,
-                block: Box::new(block),
-                elseblock: Some(Box::new(block2))
-            }
-        } else {
-            Stmt::Ifstmt {
-                expr,
-                block: Box::new(block),
-                elseblock: None
+                elseblock = Some(Box::new(self.branch_body()?));
             }
+            break;
         }
-
+        Ok(Stmt::new(StmtKind::Ifstmt {
+            branches,
+            elseblock
+        }, self.span_from(start)))
+    }
+    /// The condition of an `if` branch. It is read as a whole expression, so
+    /// `if (a + 1) * 2 > 3 {}` works; when that isn't followed by a braced body, a
+    /// condition in parentheses ends at the matching `)` instead, so a body without
+    /// braces isn't read as part of it: `if (done) ++count;` is not `if ((done)++) count;`.
+    fn condition(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index;
+        let errors = self.errors.len();
+        let expr = self.expression();
+        if self.tokens[start].ttype != LeftParen || (expr.is_ok() && self.check(&LeftCurly)) {
+            return expr;
+        }
+        self.index = start + 1;
+        self.errors.truncate(errors);
+        let expr = self.expression()?;
+        self.consume(RightParen, "Missing ')' ")?;
+        Ok(Expr::new(Grouping(Box::new(expr)), self.span_from(start)))
     }
-    fn while_stmt(&mut self) -> Stmt {
-        let expr = Box::new(self.expression());
-        let mut block = self.declaration();
+    /// The body of an `if` branch: a braced block or a single statement.
+    fn branch_body(&mut self) -> Result<Stmt, ScrapError> {
+        let start = self.index;
         if self.match_next(&[LeftCurly]) {
-            let mut stmts = Vec::new();
-            while !self.check(&RightCurly) {
-                let expr = self.declaration();
-
-                stmts.push(expr);
-                self.advance();
-            }
-
-            block = Stmt::Block(stmts);
+            Ok(Stmt::new(StmtKind::Block(self.block()?), self.span_from(start)))
+        } else {
+            self.statement()
         }
-        Stmt::WhileStmt {
+    }
+    fn while_stmt(&mut self) -> Result<Stmt, ScrapError> {
+        let start = self.index - 1;
+        let expr = Box::new(self.expression()?);
+        self.consume(LeftCurly, "expected '{' after while condition")?;
+        let block = self.loop_body()?;
+        Ok(Stmt::new(StmtKind::WhileStmt {
             expr,
             block: Box::new(block)
+        }, self.span_from(start)))
+    }
+    fn switch_stmt(&mut self) -> Result<Stmt, ScrapError> {
+        let start = self.index - 1;
+        self.consume(LeftParen, "expected '(' after 'switch'")?;
+        let subject = Box::new(self.expression()?);
+        self.consume(RightParen, "expected ')' after switch subject")?;
+        self.consume(LeftCurly, "expected '{' before switch body")?;
+        // `break` leaves a switch like it leaves a loop
+        self.loop_depth += 1;
+        let mut cases = Vec::new();
+        while !self.check(&RightCurly) && !self.is_at_end() {
+            // a case with a broken label is left out, but its body is still parsed for errors
+            let start = self.index;
+            let label = match self.case_label() {
+                Ok(value) => Some(value),
+                Err(error) => {
+                    self.errors.push(error);
+                    self.synchronize(start);
+                    None
+                }
+            };
+            let stmts = self.declarations_until(&[Case, Default]);
+            if let Some(value) = label {
+                cases.push((value, stmts));
+            }
         }
+        self.loop_depth -= 1;
+        self.consume(RightCurly, "expected '}' after switch body")?;
+        Ok(Stmt::new(StmtKind::Switch {
+            subject,
+            cases
+        }, self.span_from(start)))
+    }
+    /// A `case value:` or `default:` label; the value is None for `default`.
+    fn case_label(&mut self) -> Result<Option<Expr>, ScrapError> {
+        let value = if self.match_next(&[Case]) {
+            Some(self.expression()?)
+        } else {
+            self.consume(Default, "expected 'case' or 'default' in switch body")?;
+            None
+        };
+        self.consume(Colon, "expected ':' after case")?;
+        Ok(value)
     }
-    fn print_stmt(&mut self) -> Stmt {
-        let value = self.declaration();
-        Stmt::Print(Box::new(value))
+    fn for_stmt(&mut self) -> Result<Stmt, ScrapError> {
+        let start = self.index - 1;
+        self.consume(LeftParen, "expected '(' after 'for'")?;
+        let initializer = if self.match_next(&[Semicolon]) {
+            None
+        } else if self.match_next(&[Var]) {
+            Some(Box::new(self.variable_declaration()?))
+        } else {
+            Some(Box::new(self.expression_stmt()?))
+        };
+        let condition = if self.check(&Semicolon) {
+            None
+        } else {
+            Some(Box::new(self.expression()?))
+        };
+        self.consume(Semicolon, "expected ';' after for condition")?;
+        let increment = if self.check(&RightParen) {
+            None
+        } else {
+            Some(Box::new(self.expression()?))
+        };
+        self.consume(RightParen, "expected ')' after for clauses")?;
+        self.consume(LeftCurly, "expected '{' after for")?;
+        let block = self.loop_body()?;
+        Ok(Stmt::new(StmtKind::ForStmt {
+            initializer,
+            condition,
+            increment,
+            block: Box::new(block)
+        }, self.span_from(start)))
+    }
+    fn foreach_stmt(&mut self) -> Result<Stmt, ScrapError> {
+        let start = self.index - 1;
+        let keyword = self.previous().unwrap().clone();
+        self.consume(LeftParen, "expected '(' after 'foreach'")?;
+        let iterable = Box::new(self.expression()?);
+        self.consume(As, "expected 'as' after foreach expression")?;
+        let first = self.consume(Identifier, "expected variable name after 'as'")?;
+        let (key, value) = if self.match_next(&[DoubleArrow]) {
+            (Some(first), self.consume(Identifier, "expected variable name after '=>'")?)
+        } else {
+            (None, first)
+        };
+        self.consume(RightParen, "expected ')' after foreach variables")?;
+        self.consume(LeftCurly, "expected '{' after foreach")?;
+        let block = self.loop_body()?;
+        Ok(Stmt::new(StmtKind::Foreach {
+            keyword,
+            iterable,
+            key,
+            value,
+            block: Box::new(block)
+        }, self.span_from(start)))
+    }
+    fn break_stmt(&mut self) -> Result<Stmt, ScrapError> {
+        let start = self.index - 1;
+        let keyword = self.previous().unwrap().clone();
+        let depth = if self.match_next(&[Number]) {
+            match self.previous().unwrap().lexeme {
+                Some(obj::Int(depth)) => usize::try_from(depth).unwrap_or(0),
+                _ => 0
+            }
+        } else {
+            1
+        };
+        self.consume(Semicolon, "Missing semicolon")?;
+        if depth == 0 {
+            return Err(ScrapError::error(
+                ParserError,
+                format!("'{}' operator accepts only positive integers", keyword.literal).as_str(),
+                keyword.span
+            ));
+        } else if depth > self.loop_depth {
+            let msg = if self.loop_depth == 0 {
+                format!("'{}' not in a loop", keyword.literal)
+            } else {
+                format!("cannot '{}' {} levels", keyword.literal, depth)
+            };
+            return Err(ScrapError::error(ParserError, msg.as_str(), keyword.span));
+        }
+        Ok(if keyword.ttype == Break {
+            Stmt::new(StmtKind::Break(depth), self.span_from(start))
+        } else {
+            Stmt::new(StmtKind::Continue(depth), self.span_from(start))
+        })
+    }
+    fn return_stmt(&mut self) -> Result<Stmt, ScrapError> {
+        let start = self.index - 1;
+        let value = if self.check(&Semicolon) {
+            Expr::new(Literal(obj::Null), self.span_from(start))
+        } else {
+            self.expression()?
+        };
+        self.consume(Semicolon, "Missing semicolon")?;
+        Ok(Stmt::new(StmtKind::Return(Box::new(value)), self.span_from(start)))
+    }
+    fn unset_stmt(&mut self) -> Result<Stmt, ScrapError> {
+        let start = self.index - 1;
+        let keyword = self.previous().unwrap().clone();
+        self.consume(LeftParen, "expected '(' after 'unset'")?;
+        let (targets, _) = self.arguments()?;
+        self.consume(Semicolon, "Missing semicolon")?;
+        Ok(Stmt::new(StmtKind::Unset {
+            keyword,
+            targets
+        }, self.span_from(start)))
+    }
+    fn throw_stmt(&mut self) -> Result<Stmt, ScrapError> {
+        let start = self.index - 1;
+        let keyword = self.previous().unwrap().clone();
+        let value = self.expression()?;
+        self.consume(Semicolon, "Missing semicolon")?;
+        Ok(Stmt::new(StmtKind::Throw {
+            keyword,
+            value: Box::new(value)
+        }, self.span_from(start)))
+    }
+    fn try_stmt(&mut self) -> Result<Stmt, ScrapError> {
+        let start = self.index - 1;
+        self.consume(LeftCurly, "expected '{' after 'try'")?;
+        let block = self.block()?;
+        let mut catches = Vec::new();
+        while self.match_next(&[Catch]) {
+            self.consume(LeftParen, "expected '(' after 'catch'")?;
+            let class = self.consume(Identifier, "expected exception class name")?;
+            let variable = if self.check(&Identifier) {
+                self.advance().cloned()
+            } else {
+                None
+            };
+            self.consume(RightParen, "expected ')' after catch clause")?;
+            self.consume(LeftCurly, "expected '{' before catch block")?;
+            catches.push((class, variable, self.block()?));
+        }
+        let finally = if self.match_next(&[Finally]) {
+            self.consume(LeftCurly, "expected '{' after 'finally'")?;
+            Some(self.block()?)
+        } else {
+            None
+        };
+        if catches.is_empty() && finally.is_none() {
+            let span = self.previous().unwrap().span;
+            return Err(ScrapError::error(ParserError, "expected 'catch' or 'finally' after try block", span));
+        }
+        Ok(Stmt::new(StmtKind::Try {
+            block,
+            catches,
+            finally
+        }, self.span_from(start)))
+    }
+    fn print_stmt(&mut self) -> Result<Stmt, ScrapError> {
+        let start = self.index - 1;
+        let value = self.expression_stmt()?;
+        Ok(Stmt::new(StmtKind::Print(Box::new(value)), self.span_from(start)))
+    }
+    fn expression_stmt(&mut self) -> Result<Stmt, ScrapError> {
+        let start = self.index;
+        let expr = self.expression()?;
+        self.consume(Semicolon, "Missing semicolon")?;
+        Ok(Stmt::new(StmtKind::Expression(Box::new(expr)), self.span_from(start)))
+    }
+    fn loop_body(&mut self) -> Result<Stmt, ScrapError> {
+        let start = self.index - 1;
+        self.loop_depth += 1;
+        let block = Stmt::new(StmtKind::Block(self.block()?), self.span_from(start));
+        self.loop_depth -= 1;
+        Ok(block)
+    }
+    /// Parses a function body; loops around the function don't count for `break`.
+    fn function_body(&mut self) -> Result<Vec<Stmt>, ScrapError> {
+        let enclosing_loops = std::mem::replace(&mut self.loop_depth, 0);
+        let body = self.block()?;
+        self.loop_depth = enclosing_loops;
+        Ok(body)
+    }
+    fn block(&mut self) -> Result<Vec<Stmt>, ScrapError> {
+        let stmts = self.declarations_until(&[]);
+        self.consume(RightCurly, "expected '}' after block")?;
+        Ok(stmts)
+    }
+    /// The statements up to a '}' or one of `ends`. Each syntax error is recorded and
+    /// skipped, so one bad statement doesn't hide the ones after it.
+    fn declarations_until(&mut self, ends: &[TType]) -> Vec<Stmt> {
+        let loop_depth = self.loop_depth;
+        let mut stmts = Vec::new();
+        while !self.check(&RightCurly) && !ends.iter().any(|end| self.check(end)) && !self.is_at_end() {
+            if self.match_next(&[Semicolon]) {
+                continue;
+            }
+            let start = self.index;
+            match self.declaration() {
+                Ok(stmt) => stmts.push(stmt),
+                Err(error) => {
+                    self.errors.push(error);
+                    self.loop_depth = loop_depth;
+                    self.synchronize(start);
+                }
+            }
+        }
+        stmts
     }
 
-    fn expression(&mut self) -> Expr {
-        let expr = self.assign();
-        return expr
+    fn expression(&mut self) -> Result<Expr, ScrapError> {
+        self.assign()
     }
 
-    fn assign(&mut self) -> Expr {
-        let mut expr = self.or();
-        while self.match_next(&[Equal]) {
+    fn assign(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index;
+        let expr = self.coalesce()?;
+        if self.match_next(&[
+            Equal, PlusEqual, MinusEqual, StarEqual, SlashEqual, PercentEqual, StarStarEqual, DotEqual,
+            QuestionQuestionEqual, AmpersandEqual, PipeEqual, CaretEqual, LessLessEqual, GreaterGreaterEqual
+        ]) {
             let operator = self.previous().unwrap().clone();
-            let right = self.assign();
-            expr = Assign {
+            let right = self.assign()?;
+            return Ok(Expr::new(Assign {
                 left: Box::new(expr),
                 operator,
                 right: Box::new(right)
-            };
-            return expr
+            }, self.span_from(start)));
         }
-        expr
+        Ok(expr)
     }
 
-    fn or(&mut self) -> Expr {
-        let mut expr = self.and();
+    /// `left ?? right`, which groups to the right.
+    fn coalesce(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index;
+        let expr = self.or()?;
+        if self.match_next(&[QuestionQuestion]) {
+            let operator = self.previous().unwrap().clone();
+            let right = self.coalesce()?;
+            return Ok(Expr::new(Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right)
+            }, self.span_from(start)));
+        }
+        Ok(expr)
+    }
+
+    fn or(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index;
+        let mut expr = self.and()?;
         while self.match_next(&[Or]) {
             let operator = self.previous().unwrap().clone();
-            let right = self.and();
-            expr = Binary {
+            let right = self.and()?;
+            expr = Expr::new(Binary {
                 left: Box::new(expr),
                 operator,
                 right: Box::new(right)
-            };
-            return expr
+            }, self.span_from(start));
         }
-        expr
+        Ok(expr)
     }
 
-    fn and(&mut self) -> Expr {
-        let mut expr = self.equality();
+    fn and(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index;
+        let mut expr = self.bit_or()?;
         while self.match_next(&[And]) {
             let operator = self.previous().unwrap().clone();
-            let right = self.equality();
-            expr = Binary {
+            let right = self.bit_or()?;
+            expr = Expr::new(Binary {
                 left: Box::new(expr),
                 operator,
                 right: Box::new(right)
-            };
-            return expr
+            }, self.span_from(start));
         }
-        expr
+        Ok(expr)
     }
-    fn equality(&mut self) -> Expr {
-        let mut expr = self.comparison();
-        while self.match_next(&[BangEqual, EqualEqual]) {
+    fn bit_or(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index;
+        let mut expr = self.bit_xor()?;
+        while self.match_next(&[Pipe]) {
             let operator = self.previous().unwrap().clone();
-            let right = self.comparison();
-            expr = Binary {
+            let right = self.bit_xor()?;
+            expr = Expr::new(Binary {
                 left: Box::new(expr),
                 operator,
                 right: Box::new(right)
-            };
-            return expr
+            }, self.span_from(start));
+        }
+        Ok(expr)
+    }
+    fn bit_xor(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index;
+        let mut expr = self.bit_and()?;
+        while self.match_next(&[Caret]) {
+            let operator = self.previous().unwrap().clone();
+            let right = self.bit_and()?;
+            expr = Expr::new(Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right)
+            }, self.span_from(start));
         }
-        expr
+        Ok(expr)
+    }
+    fn bit_and(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index;
+        let mut expr = self.equality()?;
+        while self.match_next(&[Ampersand]) {
+            let operator = self.previous().unwrap().clone();
+            let right = self.equality()?;
+            expr = Expr::new(Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right)
+            }, self.span_from(start));
+        }
+        Ok(expr)
+    }
+    fn equality(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index;
+        let mut expr = self.comparison()?;
+        while self.match_next(&[BangEqual, EqualEqual, BangEqualEqual, EqualEqualEqual, Spaceship]) {
+            let operator = self.previous().unwrap().clone();
+            let right = self.comparison()?;
+            expr = Expr::new(Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right)
+            }, self.span_from(start));
+        }
+        Ok(expr)
     }
 
-    fn comparison(&mut self) -> Expr {
-        let mut expr = self.term();
+    fn comparison(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index;
+        let mut expr = self.concat()?;
         while self.match_next(&[Greater,GreaterEqual,Less,LessEqual]) {
             let operator = self.previous().unwrap().clone();
-            let right = self.term();
-            expr = Binary {
+            let right = self.concat()?;
+            expr = Expr::new(Binary {
                 left: Box::new(expr),
                 operator,
                 right: Box::new(right)
-            };
-            return expr
+            }, self.span_from(start));
         }
-        expr
+        Ok(expr)
     }
 
-    fn term(&mut self) -> Expr {
-        let mut expr = self.factor();
+    /// `a . b`, binding looser than the arithmetic and shift operators as in PHP 8
+    fn concat(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index;
+        let mut expr = self.shift()?;
+        while self.match_next(&[Dot]) {
+            let operator = self.previous().unwrap().clone();
+            let right = self.shift()?;
+            expr = Expr::new(Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right)
+            }, self.span_from(start));
+        }
+        Ok(expr)
+    }
+
+    fn shift(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index;
+        let mut expr = self.term()?;
+        while self.match_next(&[LessLess, GreaterGreater]) {
+            let operator = self.previous().unwrap().clone();
+            let right = self.term()?;
+            expr = Expr::new(Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right)
+            }, self.span_from(start));
+        }
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index;
+        let mut expr = self.factor()?;
         while self.match_next(&[Plus, Minus]) {
             let operator = self.previous().unwrap().clone();
-            let right = self.term();
-            expr = Binary {
+            let right = self.factor()?;
+            expr = Expr::new(Binary {
                 left: Box::new(expr),
                 operator,
                 right: Box::new(right)
-            };
-            return expr
+            }, self.span_from(start));
         }
-        expr
+        Ok(expr)
     }
 
-    fn factor(&mut self) -> Expr {
-        let mut expr = self.unary();
-        while self.match_next(&[Star, Slash]) {
+    fn factor(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index;
+        let mut expr = self.unary()?;
+        while self.match_next(&[Star, Slash, Percent]) {
             let operator = self.previous().unwrap().clone();
-            let right = self.factor();
-            expr = Binary {
+            let right = self.unary()?;
+            expr = Expr::new(Binary {
                 left: Box::new(expr),
                 operator,
                 right: Box::new(right)
-            };
-            return expr
+            }, self.span_from(start));
         }
-        expr
+        Ok(expr)
     }
-    fn unary(&mut self) -> Expr {
-        let expression: Expr = match self.peek().unwrap().ttype {
-            Minus | Bang => {
+    fn unary(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index;
+        match self.peek().unwrap().ttype {
+            Plus | Minus | Bang | Tilde => {
                 let operator = self.peek().unwrap().clone();
                 self.advance();
-                let right = self.unary();
-                return Unary {
+                let right = self.unary()?;
+                Ok(Expr::new(Unary {
                     operator,
                     right: Box::new(right)
-                }
+                }, self.span_from(start)))
             },
-            Number | Null | String_tok | True | False | Identifier | LeftParen => {
-                return self.parse_primary();
+            PlusPlus | MinusMinus => {
+                let operator = self.peek().unwrap().clone();
+                self.advance();
+                let target = self.unary()?;
+                Ok(Expr::new(Increment {
+                    target: Box::new(target),
+                    operator,
+                    prefix: true
+                }, self.span_from(start)))
             },
             _ => {
-                self.parse_primary()
+                self.power()
             }
+        }
+    }
+    /// `base ** exponent`, which binds tighter than a unary minus on its left and
+    /// groups to the right.
+    fn power(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index;
+        let expr = self.instanceof()?;
+        if self.match_next(&[StarStar]) {
+            let operator = self.previous().unwrap().clone();
+            let right = self.unary()?;
+            return Ok(Expr::new(Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right)
+            }, self.span_from(start)));
+        }
+        Ok(expr)
+    }
+    fn instanceof(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index;
+        let mut expr = self.call()?;
+        while self.match_next(&[Instanceof]) {
+            let operator = self.previous().unwrap().clone();
+            let right = self.call()?;
+            expr = Expr::new(Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right)
+            }, self.span_from(start));
+        }
+        Ok(expr)
+    }
+    fn call(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index;
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.match_next(&[LeftParen]) {
+                let (arguments, paren) = self.arguments()?;
+                expr = Expr::new(Call {
+                    callee: Box::new(expr),
+                    paren,
+                    arguments
+                }, self.span_from(start));
+            } else if self.match_next(&[LeftBracket]) {
+                let index = if self.check(&RightBracket) {
+                    None
+                } else {
+                    Some(Box::new(self.expression()?))
+                };
+                let bracket = self.consume(RightBracket, "expected ']' after index")?;
+                expr = Expr::new(Index {
+                    object: Box::new(expr),
+                    bracket,
+                    index
+                }, self.span_from(start));
+            } else if self.match_next(&[Arrow]) {
+                let name = self.consume(Identifier, "expected property name after '->'")?;
+                expr = Expr::new(Get {
+                    object: Box::new(expr),
+                    name
+                }, self.span_from(start));
+            } else if self.match_next(&[PlusPlus, MinusMinus]) {
+                let operator = self.previous().unwrap().clone();
+                expr = Expr::new(Increment {
+                    target: Box::new(expr),
+                    operator,
+                    prefix: false
+                }, self.span_from(start));
+            } else {
+                break;
+            }
+        }
+        Ok(expr)
+    }
+    /// Parses a call's argument list after its '(' and returns it with the closing ')'.
+    fn arguments(&mut self) -> Result<(Vec<Expr>, Token), ScrapError> {
+        let mut arguments = Vec::new();
+        if !self.check(&RightParen) {
+            loop {
+                arguments.push(self.expression()?);
+                if !self.match_next(&[Comma]) {
+                    break;
+                }
+            }
+        }
+        let paren = self.consume(RightParen, "expected ')' after arguments")?;
+        Ok((arguments, paren))
+    }
+    fn match_expr(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index - 1;
+        let keyword = self.previous().unwrap().clone();
+        self.consume(LeftParen, "expected '(' after 'match'")?;
+        let subject = Box::new(self.expression()?);
+        self.consume(RightParen, "expected ')' after match subject")?;
+        self.consume(LeftCurly, "expected '{' before match arms")?;
+        let mut arms = Vec::new();
+        let mut default = None;
+        while !self.check(&RightCurly) && !self.is_at_end() {
+            if let Err(error) = self.match_arm(&mut arms, &mut default) {
+                self.errors.push(error);
+                self.skip_match_arm();
+            }
+            if !self.match_next(&[Comma]) {
+                break;
+            }
+        }
+        self.consume(RightCurly, "expected '}' after match arms")?;
+        Ok(Expr::new(Match {
+            keyword,
+            subject,
+            arms,
+            default
+        }, self.span_from(start)))
+    }
+    /// One `conditions => result` or `default => result` arm of a match expression.
+    fn match_arm(&mut self, arms: &mut Vec<(Vec<Expr>, Expr)>, default: &mut Option<Box<Expr>>) -> Result<(), ScrapError> {
+        if self.match_next(&[Default]) {
+            let span = self.previous().unwrap().span;
+            self.consume(DoubleArrow, "expected '=>' after 'default'")?;
+            let result = self.expression()?;
+            if default.is_some() {
+                return Err(ScrapError::error(
+                    ParserError,
+                    "match expressions may only contain one default arm",
+                    span
+                ));
+            }
+            *default = Some(Box::new(result));
+        } else {
+            let mut conditions = vec![self.expression()?];
+            while self.match_next(&[Comma]) && !self.check(&DoubleArrow) {
+                conditions.push(self.expression()?);
+            }
+            self.consume(DoubleArrow, "expected '=>' after match condition")?;
+            arms.push((conditions, self.expression()?));
+        }
+        Ok(())
+    }
+    /// Skips the rest of a match arm with a syntax error, up to the ',' or '}' that ends it.
+    fn skip_match_arm(&mut self) {
+        let mut depth = 0usize;
+        while !self.is_at_end() {
+            match self.peek().unwrap().ttype {
+                Comma | RightCurly if depth == 0 => return,
+                LeftParen | LeftBracket | LeftCurly => depth += 1,
+                RightParen | RightBracket | RightCurly => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+    fn closure(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index - 1;
+        self.consume(LeftParen, "expected '(' after 'fn'")?;
+        let params = self.parameters()?;
+        let arrow = self.match_next(&[DoubleArrow]);
+        let body = if arrow {
+            vec![Stmt::new(StmtKind::Return(Box::new(self.expression()?)), self.span_from(start))]
+        } else {
+            self.consume(LeftCurly, "expected '{' before function body")?;
+            self.function_body()?
         };
-        return expression;
-
-
+        Ok(Expr::new(Closure {
+            params,
+            body,
+            arrow
+        }, self.span_from(start)))
     }
-    fn parse_primary(&mut self) -> Expr {
-        match self.peek().unwrap().ttype {
+    fn parse_primary(&mut self) -> Result<Expr, ScrapError> {
+        let start = self.index;
+        Ok(match self.peek().unwrap().ttype {
             TType::Number => {
                 self.advance();
-                return Literal(obj::Num(self.previous().unwrap().literal.parse::<f64>().unwrap()))
+                Expr::new(Literal(self.previous().unwrap().lexeme.clone().unwrap_or(obj::Null)), self.span_from(start))
             },
             TType::String_tok => {
                 self.advance();
-                return Literal(obj::Str(self.previous().unwrap().clone().literal))
+                Expr::new(Literal(self.previous().unwrap().lexeme.clone().unwrap_or(obj::Null)), self.span_from(start))
             },
             TType::True => {
                 self.advance();
-                return Literal(obj::Bool(true))
+                Expr::new(Literal(obj::Bool(true)), self.span_from(start))
             },
             TType::False => {
                 self.advance();
-                return Literal(obj::Bool(false))
+                Expr::new(Literal(obj::Bool(false)), self.span_from(start))
             },
             TType::Null => {
                 self.advance();
-                return Literal(obj::Null)
+                Expr::new(Literal(obj::Null), self.span_from(start))
             },
             TType::LeftParen => {
-                let expr = self.expression();
-                if self.peek().unwrap().ttype == TType::RightParen {
-                    self.advance();
-                } else {
-                    ScrapError::error(
-                        ParserError,
-                        "Missing ')' ",
-                        self.peek().unwrap().line,
-                        file!()
-                    );
+                self.advance();
+                let expr = self.expression()?;
+                self.consume(RightParen, "Missing ')' ")?;
+                Expr::new(Grouping(Box::new(expr)), self.span_from(start))
+            },
+            TType::LeftBracket => {
+                self.advance();
+                let mut elements = Vec::new();
+                while !self.check(&RightBracket) && !self.is_at_end() {
+                    let element = self.expression()?;
+                    if self.match_next(&[DoubleArrow]) {
+                        elements.push((Some(element), self.expression()?));
+                    } else {
+                        elements.push((None, element));
+                    }
+                    if !self.match_next(&[Comma]) {
+                        break;
+                    }
                 }
-                return Grouping(Box::new(expr))
+                self.consume(RightBracket, "expected ']' after array elements")?;
+                Expr::new(Array(elements), self.span_from(start))
             },
             TType::Identifier => {
                 self.advance();
-                return Literal(obj::Identifier(self.previous().unwrap().literal.clone()))
+                Expr::new(Literal(obj::Identifier(self.previous().unwrap().literal.clone())), self.span_from(start))
+            },
+            TType::Fn => {
+                self.advance();
+                self.closure()?
+            },
+            TType::Match => {
+                self.advance();
+                self.match_expr()?
+            },
+            TType::This => {
+                self.advance();
+                Expr::new(Literal(obj::Identifier("this".to_string())), self.span_from(start))
+            },
+            TType::Parent => {
+                let keyword = self.advance().unwrap().clone();
+                self.consume(DoubleColon, "expected '::' after 'parent'")?;
+                let method = self.consume(Identifier, "expected method name after 'parent::'")?;
+                Expr::new(Parent {
+                    keyword,
+                    method
+                }, self.span_from(start))
+            },
+            TType::New => {
+                self.advance();
+                let class = self.consume(Identifier, "expected class name after 'new'")?;
+                let arguments = if self.match_next(&[LeftParen]) {
+                    self.arguments()?.0
+                } else {
+                    Vec::new()
+                };
+                Expr::new(New {
+                    class,
+                    arguments
+                }, self.span_from(start))
             },
             _ => {
-                print!("{}", self.current_token.clone().unwrap().literal);
-                return Literal(obj::Null);
+                let token = self.peek().unwrap().clone();
+                return Err(ScrapError::error(
+                    InvalidSyntax,
+                    format!("expected expression, found '{}'", token.literal).as_str(),
+                    token.span
+                ))
             }
-        }
+        })
     }
 
     //helper functions
@@ -352,10 +1105,28 @@ impl Parser {
         false
     }
 
+    fn consume(&mut self, ttype: TType, msg: &str) -> Result<Token, ScrapError> {
+        if self.check(&ttype) {
+            return Ok(self.advance().unwrap().clone());
+        }
+        // like rustc, point a missing `;` out right after what it should end
+        // rather than at whatever comes next, which may be lines further on
+        let span = match (ttype, self.index) {
+            (Semicolon, 1..) => self.tokens[self.index - 1].span.after(),
+            _ => self.peek().unwrap().span
+        };
+        Err(ScrapError::error(ParserError, msg, span))
+    }
+
+    /// The span from the token at `start` to the last token consumed.
+    fn span_from(&self, start: usize) -> Span {
+        let end = self.index.max(start + 1) - 1;
+        self.tokens[start].span.to(self.tokens[end].span)
+    }
+
     fn advance(&mut self) -> Option<&Token> {
         if !self.is_at_end() {
             self.index += 1;
-            self.current_token = self.previous().cloned();
         }
         self.previous()
     }
@@ -378,6 +1149,12 @@ impl Parser {
         }
         &self.peek().unwrap().ttype == ttype
     }
+    fn check_next(&mut self, ttype: &TType) -> bool {
+        match self.tokens.get(self.index + 1) {
+            Some(token) => &token.ttype == ttype,
+            None => false
+        }
+    }
     fn peek(&mut self) -> Option<&Token> {
         Some(&self.tokens[self.index])
     }
@@ -0,0 +1,73 @@
+use crate::ast::Stmt;
+use crate::parser::Parser;
+use crate::scanner::Scanner;
+use crate::span::PRELUDE_FILE;
+
+/// The exception hierarchy, declared before every script runs. `line` and `trace`
+/// are filled in by the interpreter when a Throwable is created with `new`.
+pub const PRELUDE: &str = r#"
+interface Throwable {
+    fn getMessage();
+    fn getCode();
+    fn getPrevious();
+    fn getLine();
+    fn getTrace();
+}
+
+class Exception implements Throwable {
+    var message = "";
+    var code = 0;
+    var previous = Null;
+    var line = 0;
+    var trace = [];
+
+    fn __construct(message = "", code = 0, previous = Null) {
+        this->message = message;
+        this->code = code;
+        this->previous = previous;
+    }
+    fn getMessage() { return this->message; }
+    fn getCode() { return this->code; }
+    fn getPrevious() { return this->previous; }
+    fn getLine() { return this->line; }
+    fn getTrace() { return this->trace; }
+}
+
+class Error implements Throwable {
+    var message = "";
+    var code = 0;
+    var previous = Null;
+    var line = 0;
+    var trace = [];
+
+    fn __construct(message = "", code = 0, previous = Null) {
+        this->message = message;
+        this->code = code;
+        this->previous = previous;
+    }
+    fn getMessage() { return this->message; }
+    fn getCode() { return this->code; }
+    fn getPrevious() { return this->previous; }
+    fn getLine() { return this->line; }
+    fn getTrace() { return this->trace; }
+}
+
+class RuntimeException extends Exception {}
+class LogicException extends Exception {}
+class InvalidArgumentException extends LogicException {}
+
+class TypeError extends Error {}
+class ValueError extends Error {}
+class ArithmeticError extends Error {}
+class DivisionByZeroError extends ArithmeticError {}
+class UnhandledMatchError extends Error {}
+"#;
+
+/// Parses the prelude into statements for the interpreter to run.
+pub fn prelude() -> Vec<Stmt> {
+    let mut scanner = Scanner::new(PRELUDE.to_string(), PRELUDE_FILE);
+    scanner.scan_tokens().expect("the prelude scans");
+    let mut parser = Parser::new(scanner.tokens);
+    parser.parse().expect("the prelude parses");
+    parser.statements
+}
@@ -1,10 +1,8 @@
 
-use std::io;
-use std::io::ErrorKind;
-
 use crate::error::ScrapError;
-use crate::error::ScrapError::ScannerError;
+use crate::error::ErrorType::ScannerError;
 use crate::object::obj;
+use crate::span::Span;
 //use crate::Object::*;
 use crate::token::Token;
 use crate::tokentype::TType;
@@ -14,36 +12,67 @@ use crate::tokentype::TType::*;
 pub struct Scanner {
     source: Vec<char>,
     pub tokens: Vec<Token>,
+    /// the id of the file being scanned
+    file: usize,
     start: usize,
     current:usize,
     line: usize,
+    /// byte offsets of `start` and `current` in the source text
+    start_byte: usize,
+    current_byte: usize,
+    /// where the token being scanned starts
+    start_line: usize,
+    start_column: usize,
+    /// the index of the first character of the current line
+    line_start: usize,
 }
 
 
 impl Scanner {
-    pub fn new(source: String) -> Scanner {
+    pub fn new(source: String, file: usize) -> Scanner {
         Scanner {
             source: source.chars().collect(),
             tokens: Vec::new(),
+            file,
             start: 0,
             current: 0,
             line: 1,
+            start_byte: 0,
+            current_byte: 0,
+            start_line: 1,
+            start_column: 1,
+            line_start: 0,
         }
     }
-    pub fn scan_tokens(&mut self) {
+    /// Scans the whole source. A character that can't be scanned is reported
+    /// and skipped, so every such error in the file is found in one run.
+    pub fn scan_tokens(&mut self) -> Result<(), Vec<ScrapError>> {
+        let mut errors = Vec::new();
         while !self.is_at_end() {
             self.start = self.current;
-            self.scan_token()
-                .expect("could not scan tokens");
+            self.start_byte = self.current_byte;
+            self.start_line = self.line;
+            self.start_column = self.start - self.line_start + 1;
+            if let Err(error) = self.scan_token() {
+                errors.push(error);
+            }
         }
 
-        self.tokens.push(Token::new(Eof,"".to_string(), None, self.line))
+        self.start_byte = self.current_byte;
+        self.start_line = self.line;
+        self.start_column = self.current - self.line_start + 1;
+        self.tokens.push(Token::new(Eof,"".to_string(), None, self.span()));
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
     }
 
     fn is_at_end(&self) -> bool {
         self.current >= self.source.len()
     }
-    fn scan_token(&mut self) -> Result<(), std::io::Error> {
+    fn scan_token(&mut self) -> Result<(), ScrapError> {
         let c: char = self.advance();
         match c {
             '{' => self.add_token(LeftCurly),
@@ -54,11 +83,57 @@ impl Scanner {
             ']' => self.add_token(RightBracket),
             ',' => self.add_token(Comma),
             ';' => self.add_token(Semicolon),
-            '.' => self.add_token(Dot),
-            '*' => self.add_token(Star),
+            '.' => {
+                let token = if self.match_next('=') {
+                    DotEqual
+                } else {
+                    Dot
+                };
+                self.add_token(token)
+            },
+            ':' => {
+                let token = if self.match_next(':') {
+                    DoubleColon
+                } else {
+                    Colon
+                };
+                self.add_token(token)
+            },
+            '*' => {
+                let token = if self.match_next('*') {
+                    if self.match_next('=') {
+                        StarStarEqual
+                    } else {
+                        StarStar
+                    }
+                } else if self.match_next('=') {
+                    StarEqual
+                } else {
+                    Star
+                };
+                self.add_token(token)
+            },
+            '%' => {
+                let token = if self.match_next('=') {
+                    PercentEqual
+                } else {
+                    Percent
+                };
+                self.add_token(token)
+            },
+            '?' if self.match_next('?') => {
+                let token = if self.match_next('=') {
+                    QuestionQuestionEqual
+                } else {
+                    QuestionQuestion
+                };
+                self.add_token(token)
+            },
             // '$' => self.add_token(Var),
             '-' => {
-                let token = if self.match_next('=') {
+                let token = if self.match_next('>') {
+                    Arrow
+                } else if self.match_next('=') {
                     MinusEqual
                 } else if self.match_next('-') {
                     MinusMinus
@@ -70,7 +145,7 @@ impl Scanner {
             '+' =>  {
                 let token = if self.match_next('=') {
                     PlusEqual
-                } else if self.match_next('-') {
+                } else if self.match_next('+') {
                     PlusPlus
                 } else {
                     Plus
@@ -79,14 +154,26 @@ impl Scanner {
             },
             '=' =>  {
                 let token = if self.match_next('=') {
-                    EqualEqual
+                    if self.match_next('=') {
+                        EqualEqualEqual
+                    } else {
+                        EqualEqual
+                    }
+                } else if self.match_next('>') {
+                    DoubleArrow
                 } else {
                     Equal
                 };
                 self.add_token(token)
             },
             '>' =>  {
-                let token = if self.match_next('=') {
+                let token = if self.match_next('>') {
+                    if self.match_next('=') {
+                        GreaterGreaterEqual
+                    } else {
+                        GreaterGreater
+                    }
+                } else if self.match_next('=') {
                     GreaterEqual
                 } else {
                     Greater
@@ -94,8 +181,18 @@ impl Scanner {
                 self.add_token(token)
             },
             '<' =>  {
-                let token = if self.match_next('=') {
-                    LessEqual
+                let token = if self.match_next('<') {
+                    if self.match_next('=') {
+                        LessLessEqual
+                    } else {
+                        LessLess
+                    }
+                } else if self.match_next('=') {
+                    if self.match_next('>') {
+                        Spaceship
+                    } else {
+                        LessEqual
+                    }
                 } else {
                     Less
                 };
@@ -103,7 +200,11 @@ impl Scanner {
             },
             '!' =>  {
                 let token = if self.match_next('=') {
-                    BangEqual
+                    if self.match_next('=') {
+                        BangEqualEqual
+                    } else {
+                        BangEqual
+                    }
                 } else {
                     Bang
                 };
@@ -118,36 +219,56 @@ impl Scanner {
                             break;
                         }
                     }
+                } else if self.match_next('=') {
+                    self.add_token(SlashEqual)
                 } else {
                     self.add_token(Slash)
                 }
             },
             ' ' | '\r' | '\t' => {},
             '\n' => {
-                self.line += 1;
+                self.newline();
             }
             '"' => {
                 self.string()?;
             }
             '&' => {
-                if self.match_next('&') {
-                    self.add_token(And)
-                }
+                let token = if self.match_next('&') {
+                    And
+                } else if self.match_next('=') {
+                    AmpersandEqual
+                } else {
+                    Ampersand
+                };
+                self.add_token(token)
             }
             '|' => {
-                if self.match_next('|') {
-                    self.add_token(Or)
-                }
+                let token = if self.match_next('|') {
+                    Or
+                } else if self.match_next('=') {
+                    PipeEqual
+                } else {
+                    Pipe
+                };
+                self.add_token(token)
             }
+            '^' => {
+                let token = if self.match_next('=') {
+                    CaretEqual
+                } else {
+                    Caret
+                };
+                self.add_token(token)
+            }
+            '~' => self.add_token(Tilde),
             _ => {
                 if Scanner::is_digit(Some(c)) {
-                    self.number()
+                    self.number()?
                 } else if Scanner::is_alpha(Some(c)) {
                     self.identifier()
                 } else {
                     let msg = format!("unexpected character: '{c}'");
-                    ScrapError::error(ScannerError, msg.as_str() , self.line, file!());
-                    io::Error::new(ErrorKind::Other, "unexpected character");
+                    return Err(ScrapError::error(ScannerError, msg.as_str(), self.span()));
                 }
                 
             }
@@ -167,9 +288,32 @@ impl Scanner {
             "false" => Some(False),
             "while" => Some(While),
             "for" => Some(For),
+            "foreach" => Some(Foreach),
+            "as" => Some(As),
             "fn" => Some(Fn),
             "class" => Some(Class),
+            "new" => Some(New),
+            "this" => Some(This),
+            "extends" => Some(Extends),
+            "parent" => Some(Parent),
+            "instanceof" => Some(Instanceof),
+            "interface" => Some(Interface),
+            "implements" => Some(Implements),
+            "abstract" => Some(Abstract),
+            "trait" => Some(Trait),
+            "use" => Some(Use),
+            "unset" => Some(Unset),
             "return" => Some(Return),
+            "break" => Some(Break),
+            "switch" => Some(Switch),
+            "case" => Some(Case),
+            "default" => Some(Default),
+            "match" => Some(Match),
+            "continue" => Some(Continue),
+            "throw" => Some(Throw),
+            "try" => Some(Try),
+            "catch" => Some(Catch),
+            "finally" => Some(Finally),
             "echo" => Some(Echo),
             "Null" => Some(Null),
             _ => {
@@ -184,11 +328,28 @@ impl Scanner {
     fn add_token_object(&mut self, ttype: TType, literal: Option<obj/*change this to object later*/>) {
         let lexeme: String = self.source[self.start..self.current].iter().collect();
         self.tokens
-            .push(Token::new(ttype, lexeme, literal, self.line));
+            .push(Token::new(ttype, lexeme, literal, self.span()));
+    }
+    /// The span of the token being scanned.
+    fn span(&self) -> Span {
+        Span {
+            file: self.file,
+            start: self.start_byte,
+            end: self.current_byte,
+            line: self.start_line,
+            column: self.start_column,
+            end_line: self.line,
+            end_column: self.current - self.line_start + 1
+        }
+    }
+    fn newline(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
     }
     fn advance(&mut self) -> char {
         let result = *self.source.get(self.current).unwrap();
         self.current += 1;
+        self.current_byte += result.len_utf8();
         result
     }
 
@@ -196,6 +357,7 @@ impl Scanner {
         match self.source.get(self.current) {
             Some(c) if *c == expected => {
                 self.current += 1;
+                self.current_byte += c.len_utf8();
                 true
             }
             _ => false
@@ -209,22 +371,25 @@ impl Scanner {
         self.source.get(self.current + 1).copied()
     }
 
-    fn string(&mut self) -> Result<(), io::Error> {
+    fn string(&mut self) -> Result<(), ScrapError> {
         while let Some(c) = self.peek() {
-            match c {
-                '"' => {
-                    break;
-                },
-                '\n' => {
-                    self.line += 1;
-                },
-                _ => {}
+            if c == '"' {
+                break;
             }
             self.advance();
+            if c == '\n' {
+                self.newline();
+            }
         }
         if self.is_at_end() {
-            io::Error::new(ErrorKind::Other, "unterminated string");
-            ScrapError::error(ScannerError, format!("missing {}", '"').as_str(), self.line, file!())
+            // point at the opening quote
+            let span = Span {
+                end: self.start_byte + 1,
+                end_line: self.start_line,
+                end_column: self.start_column + 1,
+                ..self.span()
+            };
+            return Err(ScrapError::error(ScannerError, format!("missing {}", '"').as_str(), span));
         }
         let value: String = self.source[(self.start + 1)..(self.current)]
             .iter().collect();
@@ -233,40 +398,107 @@ impl Scanner {
         Ok(())
     }
     fn is_digit(c: Option<char>) -> bool {
-        if c >= Some('0') && c <= Some('9') {
-            true
-        } else {
-            false
-        }
+        matches!(c, Some('0'..='9'))
     }
     fn is_alpha(c: Option<char>) -> bool {
-        if c >= Some('a') && c <= Some('z') ||
-           c >= Some('A') && c <= Some('Z') ||
-           c == Some('_') {
-            true
-        } else {
-            false
-        }
+        matches!(c, Some('a'..='z') | Some('A'..='Z') | Some('_'))
     }
 
     fn is_alphanumeric(c: Option<char>) -> bool {
-        if Scanner::is_alpha(c) || Scanner::is_digit(c) {
-            true
-        } else {
-            false
-        }
+        Scanner::is_alpha(c) || Scanner::is_digit(c)
     }
-    fn number(&mut self) {
-        while Scanner::is_digit(self.peek()) {
+    /// Scans a number literal: decimal, `0x` hex, `0o` or leading-zero octal and
+    /// `0b` binary integers, and decimal floats with a fraction or an exponent.
+    /// Digits may be separated by single underscores. An integer too large
+    /// for 64 bits becomes a float.
+    fn number(&mut self) -> Result<(), ScrapError> {
+        let first = self.source[self.start];
+        let radix = match (first, self.peek()) {
+            ('0', Some('x' | 'X')) => 16,
+            ('0', Some('o' | 'O')) => 8,
+            ('0', Some('b' | 'B')) => 2,
+            _ => 10
+        };
+        let mut digits = String::new();
+        let mut is_float = false;
+        if radix == 10 {
+            digits.push(first);
+            digits.push_str(&self.digits(10));
+            if self.peek() == Some('.') && Scanner::is_digit(self.peek_next()) {
+                self.advance();
+                digits.push('.');
+                digits.push_str(&self.digits(10));
+                is_float = true;
+            }
+            let signed = matches!(self.peek_next(), Some('+' | '-'));
+            let exponent_digit = if signed { self.source.get(self.current + 2).copied() } else { self.peek_next() };
+            if matches!(self.peek(), Some('e' | 'E')) && Scanner::is_digit(exponent_digit) {
+                self.advance();
+                digits.push('e');
+                if signed {
+                    digits.push(self.advance());
+                }
+                digits.push_str(&self.digits(10));
+                is_float = true;
+            }
+        } else {
             self.advance();
+            // an underscore may not follow the prefix
+            if self.peek() != Some('_') {
+                digits = self.digits(radix);
+            }
         }
-
-        if self.peek() == Some('.') && Scanner::is_digit(self.peek_next()) {
+        // a literal runs into letters or underscores, as in `0b12`, `1_` or `0x`
+        if digits.is_empty() || matches!(self.peek(), Some(c) if c.is_alphanumeric() || c == '_') {
+            while matches!(self.peek(), Some(c) if c.is_alphanumeric() || c == '_') {
+                self.advance();
+            }
+            return Err(self.invalid_number());
+        }
+        let value = if is_float {
+            obj::Float(digits.parse().unwrap())
+        } else {
+            let (digits, radix) = match digits.strip_prefix('0') {
+                // PHP's legacy octal notation, `0755`
+                Some(octal) if radix == 10 && !octal.is_empty() => (octal.to_string(), 8),
+                _ => (digits, radix)
+            };
+            if digits.chars().any(|c| !c.is_digit(radix)) {
+                return Err(self.invalid_number());
+            }
+            match i64::from_str_radix(&digits, radix) {
+                Ok(n) => obj::Int(n),
+                Err(_) => obj::Float(digits.chars().fold(0.0, |value, c| {
+                    value * f64::from(radix) + f64::from(c.to_digit(radix).unwrap())
+                }))
+            }
+        };
+        self.add_token_object(Number, Some(value));
+        Ok(())
+    }
+    /// The error for the malformed number literal just scanned. A number token
+    /// still takes its place, so the parser doesn't report the literal again.
+    fn invalid_number(&mut self) -> ScrapError {
+        let lexeme: String = self.source[self.start..self.current].iter().collect();
+        self.add_token_object(Number, Some(obj::Int(0)));
+        let msg = format!("invalid numeric literal '{lexeme}'");
+        ScrapError::error(ScannerError, msg.as_str(), self.span())
+    }
+    /// Consumes the digits of a number in `radix` and the single underscores
+    /// between them, returning the digits.
+    fn digits(&mut self, radix: u32) -> String {
+        let mut digits = String::new();
+        // octal digits are checked once the whole literal is known
+        let radix = if radix == 8 { 10 } else { radix };
+        while let Some(c) = self.peek() {
+            if c.is_digit(radix) {
+                digits.push(c);
+            } else if c != '_' || !self.peek_next().is_some_and(|next| next.is_digit(radix)) {
+                break;
+            }
             self.advance();
         }
-        let value: String = self.source[self.start..self.current].iter().collect();
-        let num: f64 = value.parse().unwrap();
-        self.add_token_object(Number, Some(obj::Num(num)))
+        digits
     }
 
     fn identifier(&mut self) {
@@ -274,8 +506,8 @@ impl Scanner {
             self.advance();
         }
         let text: String = self.source[self.start..self.current].iter().collect();
-        if let Some(TType) = Scanner::keywords(text.as_str()) {
-            self.add_token(TType);
+        if let Some(ttype) = Scanner::keywords(text.as_str()) {
+            self.add_token(ttype);
         } else {
             self.add_token(Identifier);
         }
@@ -0,0 +1,43 @@
+/// The id of the script given on the command line.
+pub const SCRIPT_FILE: usize = 0;
+/// The id of the prelude, see `prelude.rs`.
+pub const PRELUDE_FILE: usize = 1;
+
+/// A range of source text: the file it is in, its byte offsets and the
+/// lines and columns (both counted from 1) where it starts and ends.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    pub file: usize,
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+    /// the line and column just past the last character
+    pub end_line: usize,
+    pub end_column: usize
+}
+
+impl Span {
+    /// The span from the start of this one to the end of `other`.
+    pub fn to(self, other: Span) -> Span {
+        if other.end <= self.end {
+            return self;
+        }
+        Span {
+            end: other.end,
+            end_line: other.end_line,
+            end_column: other.end_column,
+            ..self
+        }
+    }
+
+    /// The empty span just after the end of this one.
+    pub fn after(self) -> Span {
+        Span {
+            start: self.end,
+            line: self.end_line,
+            column: self.end_column,
+            ..self
+        }
+    }
+}
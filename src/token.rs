@@ -1,4 +1,5 @@
 use crate::object::obj;
+use crate::span::Span;
 use crate::tokentype::TType;
 
 #[derive(Debug,Clone, PartialEq)]
@@ -6,16 +7,16 @@ pub struct Token {
     pub ttype: TType,
     pub literal: String,
     pub lexeme: Option<obj>,
-    pub line: usize,
+    pub span: Span,
 }
 
 impl Token {
-    pub fn new(ttype: TType, literal: String, lexeme: Option<obj>, line: usize) -> Token {
+    pub fn new(ttype: TType, literal: String, lexeme: Option<obj>, span: Span) -> Token {
         Token {
             ttype,
             literal,
             lexeme,
-            line,
+            span,
         }
     }
 }
\ No newline at end of file
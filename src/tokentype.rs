@@ -12,22 +12,50 @@ pub enum TType {
     Minus,
     Equal,
     EqualEqual,
+    EqualEqualEqual,
+    DoubleArrow,
     PlusEqual,
     MinusEqual,
     PlusPlus,
     MinusMinus,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
+    StarStarEqual,
+    DotEqual,
+    QuestionQuestion,
+    QuestionQuestionEqual,
+    AmpersandEqual,
+    PipeEqual,
+    CaretEqual,
+    LessLessEqual,
+    GreaterGreaterEqual,
+    Arrow,
     Greater,
     Less,
     GreaterEqual,
     LessEqual,
+    Spaceship,
     Bang,
     BangEqual,
+    BangEqualEqual,
     Semicolon,
     Dot,
+    Colon,
+    DoubleColon,
     Star,
+    StarStar,
     Slash,
+    Percent,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
+    LessLess,
+    GreaterGreater,
 
     Identifier,
+    #[allow(non_camel_case_types)]
     String_tok,
     Number,
 
@@ -39,13 +67,36 @@ pub enum TType {
     ElseIf,
     If,
     For,
+    Foreach,
+    As,
     While,
     Return,
+    Break,
+    Switch,
+    Case,
+    Default,
+    Match,
+    Continue,
     True,
     False,
     Null,
     Echo,
     Fn,
+    New,
+    This,
+    Extends,
+    Parent,
+    Instanceof,
+    Interface,
+    Implements,
+    Abstract,
+    Trait,
+    Use,
+    Unset,
+    Throw,
+    Try,
+    Catch,
+    Finally,
 
     Eof
 
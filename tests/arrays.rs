@@ -0,0 +1,79 @@
+mod common;
+
+use common::{output, run};
+
+#[test]
+fn arrays_are_read_written_and_appended() {
+    let script = "
+        var a = [1, 2, 3];
+        echo a[1];
+        a[1] = 20;
+        a[] = 4;
+        echo a;
+        echo count(a);
+        echo count([]);
+    ";
+    assert_eq!(output(script), "2\n[1, 20, 3, 4]\n4\n0\n");
+}
+
+#[test]
+fn arrays_are_copied_on_assignment() {
+    let script = "
+        var a = [1, 2];
+        var b = a;
+        b[0] = 100;
+        echo a[0];
+        echo b[0];
+    ";
+    assert_eq!(output(script), "1\n100\n");
+}
+
+#[test]
+fn nested_arrays_and_properties_can_be_written() {
+    let script = "
+        var grid = [[1, 2], [3, 4]];
+        grid[1][0] = 30;
+        grid[1][] = 5;
+        echo grid;
+        class Bag {
+            var items = [];
+            fn add(x) { this->items[] = x; return this; }
+        }
+        var bag = new Bag();
+        bag->add(1)->add(2);
+        echo bag->items;
+    ";
+    assert_eq!(output(script), "[[1, 2], [30, 4, 5]]\n[1, 2]\n");
+}
+
+#[test]
+fn reading_past_the_end_is_an_error() {
+    let run = run("var a = [1, 2, 3]; echo a[10];");
+    assert_eq!(run.stdout, "");
+    assert!(run.stderr.contains("undefined array key 10"));
+}
+
+#[test]
+fn variables_named_like_builtins_and_classes_leave_them_alone() {
+    let script = "
+        fn tally(items) {
+            count = 0;
+            foreach (items as item) { count += 1; }
+            return count;
+        }
+        echo tally([1, 2, 3]);
+        echo count([1, 2]);
+        fn clobber() { Exception = 1; }
+        clobber();
+        try { throw new Exception(\"still a class\"); } catch (Exception e) { echo e->getMessage(); }
+        unset(count);
+        echo count([1]);
+    ";
+    assert_eq!(output(script), "3\n2\nstill a class\n1\n");
+}
+
+#[test]
+fn misspelled_variables_are_not_matched_against_builtins() {
+    let run = run("var count1 = 1; echo coutn1;");
+    assert!(run.stderr.contains("did you mean `count1`?"));
+}
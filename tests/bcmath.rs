@@ -0,0 +1,67 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn bcmath_works_beyond_64_bits() {
+    let script = "
+        echo bcadd(\"123456789012345678901234567890\", \"987654321098765432109876543210\");
+        echo bcmul(\"123456789123456789\", \"987654321987654321\");
+        echo bcpow(\"2\", \"100\");
+    ";
+    let expected = "1111111110111111111011111111100\n121932631356500531347203169112635269\n\
+                    1267650600228229401496703205376\n";
+    assert_eq!(output(script), expected);
+}
+
+#[test]
+fn results_are_truncated_to_the_scale() {
+    let script = "
+        echo bcadd(\"0.1\", \"0.2\", 1);
+        echo bcsub(\"1\", \"2.5\", 2);
+        echo bcmul(\"-1.25\", \"0.5\", 4);
+        echo bcdiv(\"10\", \"3\", 20);
+        echo bcdiv(\"-7\", \"2\");
+        echo bcmod(\"-10\", \"3\");
+        echo bcmod(\"5.7\", \"1.3\", 1);
+        echo bcpow(\"2\", \"-2\", 4);
+        echo bcmul(\"-0.001\", \"1\", 2);
+    ";
+    let expected = "0.3\n-1.50\n-0.6250\n3.33333333333333333333\n-3\n-1\n0.5\n0.2500\n0.00\n";
+    assert_eq!(output(script), expected);
+}
+
+#[test]
+fn bcscale_sets_the_default_scale() {
+    let script = "
+        echo bcscale();
+        echo bcscale(3);
+        echo bcadd(\"1\", \"2\");
+        echo bcdiv(\"2\", \"3\");
+        echo bccomp(\"1.001\", \"1.0001\");
+        echo bccomp(\"1.001\", \"1.0001\", 2);
+    ";
+    assert_eq!(output(script), "0\n0\n3.000\n0.666\n1\n0\n");
+}
+
+#[test]
+fn numeric_strings_compare_exactly() {
+    let script = "
+        if \"99999999999999999999\" > \"99999999999999999998\" { echo \"greater\"; }
+        if \"1.50\" == \"1.5\" { echo \"equal\"; }
+    ";
+    assert_eq!(output(script), "greater\nequal\n");
+}
+
+#[test]
+fn bad_arguments_throw() {
+    let script = "
+        try { bcdiv(\"1\", \"0\"); } catch (DivisionByZeroError e) { echo e->getMessage(); }
+        try { bcadd(\"1x\", \"0\"); } catch (ValueError e) { echo e->getMessage(); }
+        try { bcpow(\"0\", \"-1\"); } catch (DivisionByZeroError e) { echo e->getMessage(); }
+        try { bcpow(\"2\", \"1.5\"); } catch (ValueError e) { echo e->getMessage(); }
+    ";
+    let expected = "Division by zero\nbcadd(): argument #1 is not well-formed\nNegative power of zero\n\
+                    bcpow(): argument #2 must be an integer that fits in 64 bits\n";
+    assert_eq!(output(script), expected);
+}
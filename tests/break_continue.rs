@@ -0,0 +1,43 @@
+mod common;
+
+use common::{output, run};
+
+#[test]
+fn break_and_continue_the_innermost_loop() {
+    let script = "
+        var i = 0;
+        while true {
+            i = i + 1;
+            if i == 3 { continue; }
+            if i > 5 { break; }
+            echo i;
+        }
+        foreach ([1, 2, 3, 4] as n) {
+            if n == 2 { continue; }
+            if n == 4 { break; }
+            echo n;
+        }
+    ";
+    assert_eq!(output(script), "1\n2\n4\n5\n1\n3\n");
+}
+
+#[test]
+fn a_depth_leaves_outer_loops() {
+    let script = "
+        for (var a = 0; a < 3; a = a + 1) {
+            for (var b = 0; b < 3; b = b + 1) {
+                if b == 1 { continue 2; }
+                if a == 2 { break 2; }
+                echo a;
+            }
+        }
+        echo \"done\";
+    ";
+    assert_eq!(output(script), "0\n1\ndone\n");
+}
+
+#[test]
+fn break_outside_a_loop_is_a_syntax_error() {
+    assert!(run("break;").stderr.contains("'break' not in a loop"));
+    assert!(run("while true { break 0; }").stderr.contains("'break' operator accepts only positive integers"));
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use common::output;
+
+const POINT: &str = "
+    class Point {
+        var x;
+        var y = 0;
+        fn __construct(x, y) {
+            this->x = x;
+            this->y = y;
+        }
+        fn len2() {
+            return this->x * this->x + this->y * this->y;
+        }
+        fn scale(k) {
+            this->x = this->x * k;
+            this->y = this->y * k;
+            return this;
+        }
+    }
+";
+
+#[test]
+fn constructors_and_methods_use_this() {
+    let script = format!("{POINT}
+        var p = new Point(3, 4);
+        echo p->len2();
+        echo p->scale(2)->len2();
+        echo p->x;
+    ");
+    assert_eq!(output(&script), "25\n100\n6\n");
+}
+
+#[test]
+fn instances_are_shared_by_reference() {
+    let script = format!("{POINT}
+        var p = new Point(3, 4);
+        var q = p;
+        q->x = 100;
+        echo p->x;
+    ");
+    assert_eq!(output(&script), "100\n");
+}
+
+#[test]
+fn properties_can_be_added_to_any_instance() {
+    let script = "
+        class Empty {}
+        var e = new Empty;
+        e->name = \"dynamic\";
+        echo e->name;
+    ";
+    assert_eq!(output(script), "dynamic\n");
+}
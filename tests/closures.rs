@@ -0,0 +1,66 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn arrow_functions_return_their_expression() {
+    let script = "
+        var double = fn(x) => x * 2;
+        echo double(21);
+        echo (fn(a, b) => a + b)(1, 2);
+    ";
+    assert_eq!(output(script), "42\n3\n");
+}
+
+#[test]
+fn closures_keep_their_scope_alive() {
+    let script = "
+        fn make_counter() {
+            var count = 0;
+            return fn () {
+                count = count + 1;
+                return count;
+            };
+        }
+        var c = make_counter();
+        c();
+        c();
+        echo c();
+        var d = make_counter();
+        echo d();
+    ";
+    assert_eq!(output(script), "3\n1\n");
+}
+
+#[test]
+fn closures_can_be_passed_as_callbacks() {
+    let script = "
+        fn apply(f, v) {
+            return f(v);
+        }
+        var factor = 3;
+        echo apply(fn(x) => x * factor, 5);
+        echo apply(fn (x) { return x - 1; }, 5);
+    ";
+    assert_eq!(output(script), "15\n4\n");
+}
+
+#[test]
+fn arrow_functions_capture_by_value() {
+    let script = "
+        var n = 1;
+        var arrow = fn () => n * 10;
+        var closure = fn () { return n * 10; };
+        n = 2;
+        echo arrow();
+        echo closure();
+        var fns = [];
+        var v = 1;
+        while v <= 3 {
+            fns[] = fn () => v;
+            v++;
+        }
+        foreach (fns as f) { echo f(); }
+    ";
+    assert_eq!(output(script), "10\n20\n1\n2\n3\n");
+}
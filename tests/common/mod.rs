@@ -0,0 +1,54 @@
+//! Runs scripts through the interpreter binary for the script-level tests.
+#![allow(dead_code)]
+
+use std::fs;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// What running a script did.
+pub struct Run {
+    /// what the script printed, without the timing line a successful run ends with
+    pub stdout: String,
+    pub stderr: String,
+    pub code: i32
+}
+
+/// Runs `source` as a script file, passing `flags` after its path.
+pub fn run_with(source: &str, flags: &[&str]) -> Run {
+    static SCRIPTS: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "nextphp-{}-{}.scrp",
+        std::process::id(),
+        SCRIPTS.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::write(&path, source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_NextPHP"))
+        .arg("scrap")
+        .arg(&path)
+        .args(flags)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+    let mut stdout = String::from_utf8(output.stdout).unwrap();
+    if output.status.success() {
+        let timing = stdout.trim_end_matches('\n').rfind('\n').map_or(0, |newline| newline + 1);
+        stdout.truncate(timing);
+    }
+    Run {
+        stdout,
+        stderr: String::from_utf8(output.stderr).unwrap(),
+        code: output.status.code().unwrap_or(-1)
+    }
+}
+
+pub fn run(source: &str) -> Run {
+    run_with(source, &[])
+}
+
+/// What `source` prints, failing the test if the script doesn't run to the end.
+pub fn output(source: &str) -> String {
+    let run = run(source);
+    assert_eq!(run.code, 0, "the script failed:\n{}{}", run.stdout, run.stderr);
+    run.stdout
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn compound_operators_update_variables() {
+    let script = "
+        var a = 5;
+        a += 3; echo a;
+        a -= 1; echo a;
+        a *= 2; echo a;
+        a /= 7; echo a;
+        var s = \"ab\";
+        s .= \"cd\"; echo s;
+        var c = (a += 1); echo c;
+    ";
+    assert_eq!(output(script), "8\n7\n14\n2\nabcd\n3\n");
+}
+
+#[test]
+fn increment_and_decrement_return_old_or_new_values() {
+    let script = "
+        var a = 3;
+        echo a++;
+        echo a;
+        echo ++a;
+        echo a--;
+        echo --a;
+        for (var i = 0; i < 3; i++) { echo i; }
+    ";
+    assert_eq!(output(script), "3\n4\n5\n5\n3\n0\n1\n2\n");
+}
+
+#[test]
+fn elements_and_properties_are_targets_too() {
+    let script = "
+        var arr = [1, 2, 3];
+        arr[1] += 10;
+        echo arr[2]++;
+        echo ++arr[0];
+        echo arr;
+        class P { var n = 0; }
+        var p = new P();
+        p->n += 5; p->n++; echo p->n;
+        echo --p->n;
+    ";
+    assert_eq!(output(script), "3\n2\n[2, 12, 4]\n6\n5\n");
+}
+
+#[test]
+fn null_coalescing_reads_and_assigns() {
+    let script = "
+        var m = [\"x\" => 1];
+        m[\"y\"] ??= 7;
+        m[\"x\"] ??= 9;
+        echo m;
+        var u = Null;
+        u ??= \"set\"; echo u;
+        echo undefined_name ?? \"fallback\";
+        echo m[\"zz\"] ?? m[\"x\"] ?? 0;
+    ";
+    assert_eq!(output(script), "[x => 1, y => 7]\nset\nfallback\n1\n");
+}
+
+#[test]
+fn the_target_is_evaluated_once() {
+    let script = "
+        var i = 0;
+        var a = [10, 20, 30];
+        a[i++] += 1;
+        echo i;
+        echo a;
+        var calls = 0;
+        fn f() { calls += 1; return 1; }
+        var b = [1, 2, 3];
+        b[f()] += 5;
+        b[f()]++;
+        var c = [];
+        c[f()] ??= 7;
+        c[f()] ??= 8;
+        echo calls;
+        echo b;
+        echo c;
+    ";
+    assert_eq!(output(script), "1\n[11, 20, 30]\n4\n[1, 8, 3]\n[1 => 7]\n");
+}
+
+#[test]
+fn strings_and_bools_are_incremented_like_php() {
+    let script = "
+        var s = \"5\"; s++; echo s;
+        var f = \"1.5\"; f--; echo f;
+        var b = true; b++; echo b;
+        var a = \"a9\"; a++; echo a;
+        var z = \"Zz\"; ++z; echo z;
+        var e = \"\"; e++; echo e;
+        var word = \"abc\"; word--; echo word;
+    ";
+    assert_eq!(output(script), "6\n0.5\n1\nb0\nAAa\n1\nabc\n");
+}
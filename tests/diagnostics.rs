@@ -0,0 +1,69 @@
+mod common;
+
+use common::run;
+
+/// The rendered diagnostics, with the script's temporary path taken out.
+fn rendered(source: &str) -> String {
+    run(source)
+        .stderr
+        .lines()
+        .map(|line| match line.split_once("--> ") {
+            Some((gutter, location)) => format!("{gutter}--> script{}", &location[location.find(':').unwrap()..]),
+            None => line.to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn errors_show_the_source_line_with_a_caret() {
+    let expected = "\
+error[E0003]: expected expression, found ';'
+ --> script:1:9
+  |
+1 | echo 1 +;
+  |         ^
+";
+    assert_eq!(rendered("echo 1 +;\n"), expected);
+}
+
+#[test]
+fn the_caret_underlines_the_whole_span_and_help_follows() {
+    let expected = "\
+error[E0004]: undefined variable 'totl'
+ --> script:2:6
+  |
+2 | echo totl + 2;
+  |      ^^^^
+  |
+  = help: did you mean `total`?
+";
+    assert_eq!(rendered("var total = 1;\necho totl + 2;\n"), expected);
+}
+
+#[test]
+fn the_gutter_widens_with_the_line_number() {
+    let source = format!("{}echo nope;\n", "\n".repeat(11));
+    let stderr = rendered(&source);
+    assert!(stderr.contains("  --> script:12:6\n   |\n12 | echo nope;\n   |      ^^^^"), "{stderr}");
+}
+
+#[test]
+fn a_missing_semicolon_is_pointed_out_after_the_previous_token() {
+    let expected = "\
+error[E0002]: Missing semicolon
+ --> script:1:6
+  |
+1 | x = 1
+  |      ^
+";
+    assert_eq!(rendered("x = 1\n\necho x;\n"), expected);
+}
+
+#[test]
+fn a_missing_semicolon_is_placed_by_characters_and_lines() {
+    let stderr = rendered("echo \"héllo\"\necho 1;\n");
+    assert!(stderr.contains(" --> script:1:13\n"), "{stderr}");
+    let stderr = rendered("echo \"two\nlines\"\necho 1;\n");
+    assert!(stderr.contains(" --> script:2:7\n  |\n2 | lines\"\n  |       ^\n"), "{stderr}");
+}
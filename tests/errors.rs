@@ -0,0 +1,37 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn syntax_errors_exit_with_65_before_anything_runs() {
+    let run = run("echo 1; echo @;");
+    assert_eq!(run.code, 65);
+    assert_eq!(run.stdout, "");
+    assert!(run.stderr.contains("unexpected character: '@'"));
+
+    let run = common::run("echo 1; echo 1 +;");
+    assert_eq!(run.code, 65);
+    assert_eq!(run.stdout, "");
+    assert!(run.stderr.contains("expected expression, found ';'"));
+}
+
+#[test]
+fn runtime_errors_exit_with_70_after_earlier_output() {
+    let run = run("echo 1; echo nope; echo 2;");
+    assert_eq!(run.code, 70);
+    assert_eq!(run.stdout, "1\n");
+    assert!(run.stderr.contains("undefined variable 'nope'"));
+}
+
+#[test]
+fn uncaught_exceptions_exit_with_255() {
+    let run = run("echo 1; throw new Exception(\"x\");");
+    assert_eq!(run.code, 255);
+    assert_eq!(run.stdout, "1\n");
+    assert!(run.stderr.contains("Uncaught Exception: x"));
+}
+
+#[test]
+fn successful_scripts_exit_with_0() {
+    assert_eq!(run("echo 1;").code, 0);
+}
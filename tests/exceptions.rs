@@ -0,0 +1,103 @@
+mod common;
+
+use common::{output, run};
+
+#[test]
+fn catch_picks_the_first_matching_handler() {
+    let script = "
+        fn risky(n) {
+            if n > 2 {
+                throw new InvalidArgumentException(\"too big\", 7);
+            }
+            return n;
+        }
+        try {
+            echo risky(1);
+            echo risky(5);
+            echo \"not reached\";
+        } catch (RuntimeException e) {
+            echo \"wrong handler\";
+        } catch (LogicException e) {
+            echo e->getMessage();
+            echo e->getCode();
+        } finally {
+            echo \"done\";
+        }
+    ";
+    assert_eq!(output(script), "1\ntoo big\n7\ndone\n");
+}
+
+#[test]
+fn finally_runs_on_return_and_can_override_it() {
+    let script = "
+        fn outer() {
+            try {
+                return \"try\";
+            } finally {
+                echo \"finally\";
+            }
+        }
+        echo outer();
+        var r = fn () {
+            try { return \"try\"; } finally { return \"finally wins\"; }
+        };
+        echo r();
+    ";
+    assert_eq!(output(script), "finally\ntry\nfinally wins\n");
+}
+
+#[test]
+fn user_exceptions_extend_the_builtin_hierarchy() {
+    let script = "
+        class MyError extends Exception {
+            fn __construct(what) { parent::__construct(what); }
+        }
+        try { throw new MyError(\"bad\"); } catch (Throwable t) { echo t->getMessage(); }
+        try {
+            echo match (3) { 1 => \"a\" };
+        } catch (Error e) {
+            echo \"unhandled match\";
+        }
+    ";
+    assert_eq!(output(script), "bad\nunhandled match\n");
+}
+
+#[test]
+fn uncaught_exceptions_stop_the_script() {
+    let script = "
+        fn deep() { throw new Exception(\"uncaught!\"); }
+        deep();
+        echo \"never\";
+    ";
+    let run = run(script);
+    assert_eq!(run.stdout, "");
+    assert!(run.stderr.contains("uncaught!"));
+}
+
+#[test]
+fn deep_recursion_runs_and_runaway_recursion_stops() {
+    let script = "
+        fn depth(n) {
+            if n == 0 { return 0; }
+            return depth(n - 1) + 1;
+        }
+        echo depth(4000);
+    ";
+    assert_eq!(output(script), "4000\n");
+
+    let run = run("fn forever(n) { return forever(n + 1); } forever(0);");
+    assert_eq!(run.code, 70);
+    assert!(run.stderr.contains("maximum call depth of 5000 reached in forever()"));
+}
+
+#[test]
+fn recursion_inside_nested_expressions_stops_before_the_stack_runs_out() {
+    let script = format!(
+        "fn nested(n) {{ return {}nested(n + 1){} + 1; }} nested(0);",
+        "(".repeat(60),
+        ")".repeat(60)
+    );
+    let run = run(&script);
+    assert_eq!(run.code, 70);
+    assert!(run.stderr.contains("check for recursion that never stops"), "{}", run.stderr);
+}
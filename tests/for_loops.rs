@@ -0,0 +1,63 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn for_runs_initializer_condition_and_increment() {
+    let script = "
+        for (var i = 0; i < 3; i = i + 1) {
+            echo i;
+        }
+        var j = 0;
+        for (; j < 2;) { j = j + 1; }
+        echo j;
+        var sum = 0;
+        for (sum = 0; sum < 100; sum = sum + 30) {}
+        echo sum;
+    ";
+    assert_eq!(output(script), "0\n1\n2\n2\n120\n");
+}
+
+#[test]
+fn the_initializer_is_local_to_the_loop() {
+    let script = "
+        var i = \"outer\";
+        for (var i = 10; i > 8; i = i - 1) { echo i; }
+        echo i;
+    ";
+    assert_eq!(output(script), "10\n9\nouter\n");
+}
+
+#[test]
+fn return_leaves_a_for() {
+    let script = "
+        fn first_over(list, limit) {
+            for (var k = 0; k < count(list); k = k + 1) {
+                if list[k] > limit {
+                    return list[k];
+                }
+            }
+            return 0;
+        }
+        echo first_over([1, 3, 4, 5], 3);
+    ";
+    assert_eq!(output(script), "4\n");
+}
+
+#[test]
+fn closures_keep_the_value_of_their_iteration() {
+    let script = "
+        var fns = [];
+        for (var i = 0; i < 3; i++) {
+            fns[] = fn () { return i; };
+        }
+        foreach (fns as f) { echo f(); }
+        var total = 0;
+        for (var j = 0; j < 4; j++) {
+            total += j;
+            if j == 1 { j = 2; }
+        }
+        echo total;
+    ";
+    assert_eq!(output(script), "0\n1\n2\n4\n");
+}
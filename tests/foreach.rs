@@ -0,0 +1,66 @@
+mod common;
+
+use common::{output, run};
+
+#[test]
+fn foreach_visits_values_and_keys_in_order() {
+    let script = "
+        foreach ([1, 2, 3] as n) {
+            echo n;
+        }
+        var ages = [\"ann\" => 30, \"bob\" => 25];
+        foreach (ages as name => age) {
+            echo name;
+            echo age;
+        }
+        foreach ([] as v) { echo \"never\"; }
+    ";
+    assert_eq!(output(script), "1\n2\n3\nann\n30\nbob\n25\n");
+}
+
+#[test]
+fn return_leaves_a_foreach() {
+    let script = "
+        fn find(list, wanted) {
+            foreach (list as i => item) {
+                if item == wanted {
+                    return i;
+                }
+            }
+            return -1;
+        }
+        echo find([5, 6, 7], 7);
+        echo find([5, 6, 7], 8);
+    ";
+    assert_eq!(output(script), "2\n-1\n");
+}
+
+#[test]
+fn foreach_iterates_over_a_copy() {
+    let script = "
+        var arr = [1, 2];
+        foreach (arr as v) {
+            arr[] = v;
+        }
+        echo count(arr);
+    ";
+    assert_eq!(output(script), "4\n");
+}
+
+#[test]
+fn foreach_needs_an_array() {
+    let run = run("foreach (\"abc\" as v) {}");
+    assert!(run.stderr.contains("foreach() argument must be an array, string given"));
+}
+
+#[test]
+fn closures_keep_the_value_of_their_iteration() {
+    let script = "
+        var fns = [];
+        foreach ([1, 2, 3] as v) {
+            fns[] = fn () { return v * 10; };
+        }
+        foreach (fns as f) { echo f(); }
+    ";
+    assert_eq!(output(script), "10\n20\n30\n");
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::{output, run};
+
+#[test]
+fn functions_take_parameters_and_return() {
+    let script = "
+        fn add(a, b) {
+            return a + b;
+        }
+        echo add(2, 3);
+        echo add(add(1, 2), 4);
+    ";
+    assert_eq!(output(script), "5\n7\n");
+}
+
+#[test]
+fn functions_can_recurse() {
+    let script = "
+        fn fib(n) {
+            if n < 2 {
+                return n;
+            }
+            return fib(n - 1) + fib(n - 2);
+        }
+        echo fib(15);
+    ";
+    assert_eq!(output(script), "610\n");
+}
+
+#[test]
+fn return_leaves_nested_loops_and_ifs() {
+    let script = "
+        fn first_square_over(limit) {
+            var i = 0;
+            while true {
+                if i * i > limit {
+                    return i;
+                }
+                i = i + 1;
+            }
+        }
+        echo first_square_over(50);
+        echo \"after\";
+    ";
+    assert_eq!(output(script), "8\nafter\n");
+}
+
+#[test]
+fn declarations_can_be_used_before_they_appear() {
+    let script = "
+        echo null_check(Null);
+        var dog = new Dog();
+        echo dog->speak();
+        class Dog extends Animal {
+            fn speak() { return \"woof\"; }
+        }
+        abstract class Animal {
+            abstract fn speak();
+        }
+        fn null_check(x) {
+            return x == Null;
+        }
+    ";
+    assert_eq!(output(script), "1\nwoof\n");
+}
+
+#[test]
+fn calling_an_unknown_function_names_it() {
+    let run = run("fn null_check(x) { return x == Null; } echo nul_check(1);");
+    assert_eq!(run.code, 70);
+    assert!(run.stderr.contains("undefined function 'nul_check'"));
+    assert!(run.stderr.contains("did you mean `null_check`?"));
+}
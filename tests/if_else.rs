@@ -0,0 +1,63 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn elseif_and_else_if_chains() {
+    let script = "
+        fn grade(n) {
+            if n >= 90 {
+                return \"A\";
+            } elseif n >= 80 {
+                return \"B\";
+            } else if (n >= 70) {
+                return \"C\";
+            } elseif n >= 60 return \"D\";
+            else return \"F\";
+        }
+        echo grade(95);
+        echo grade(85);
+        echo grade(75);
+        echo grade(65);
+        echo grade(10);
+    ";
+    assert_eq!(output(script), "A\nB\nC\nD\nF\n");
+}
+
+#[test]
+fn branches_without_braces() {
+    let script = "
+        if (true) echo \"one\";
+        if false echo \"no\"; else echo \"two\";
+        for (var i = 0; i < 4; i = i + 1) {
+            if i == 1 continue;
+            if i == 3 break;
+            echo i;
+        }
+    ";
+    assert_eq!(output(script), "one\ntwo\n0\n2\n");
+}
+
+#[test]
+fn a_parenthesised_condition_ends_before_a_brace_less_body() {
+    let script = "
+        var count = 0;
+        var done = true;
+        if (done) ++count;
+        if (done) -1;
+        echo count;
+    ";
+    assert_eq!(output(script), "1\n");
+}
+
+#[test]
+fn a_parenthesised_condition_can_go_on_before_a_braced_body() {
+    let script = "
+        var a = 3;
+        var b = true;
+        if (a == 3) && b { echo \"and\"; }
+        if (a + 1) * 2 > 3 { echo \"arithmetic\"; }
+        if (a) && false { echo \"no\"; } elseif (a - 3) || b { echo \"elseif\"; }
+    ";
+    assert_eq!(output(script), "and\narithmetic\nelseif\n");
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use common::output;
+
+const ANIMALS: &str = "
+    class Animal {
+        var name;
+        var legs = 4;
+        fn __construct(name) {
+            this->name = name;
+        }
+        fn sound() {
+            return \"...\";
+        }
+        fn speak() {
+            return this->sound();
+        }
+    }
+    class Dog extends Animal {
+        var breed = \"mutt\";
+        fn __construct(name, breed) {
+            parent::__construct(name);
+            this->breed = breed;
+        }
+        fn sound() {
+            return \"woof\";
+        }
+    }
+    class Puppy extends Dog {
+        fn sound() {
+            return parent::sound();
+        }
+    }
+";
+
+#[test]
+fn subclasses_inherit_properties_and_parent_constructors() {
+    let script = format!("{ANIMALS}
+        var d = new Dog(\"Rex\", \"lab\");
+        echo d->name;
+        echo d->legs;
+        echo d->breed;
+    ");
+    assert_eq!(output(&script), "Rex\n4\nlab\n");
+}
+
+#[test]
+fn overridden_methods_are_called_through_this_and_parent() {
+    let script = format!("{ANIMALS}
+        echo new Animal(\"x\")->speak();
+        echo new Dog(\"Rex\", \"lab\")->speak();
+        echo new Puppy(\"Bit\", \"pug\")->speak();
+    ");
+    assert_eq!(output(&script), "...\nwoof\nwoof\n");
+}
+
+#[test]
+fn instanceof_follows_the_class_chain() {
+    let script = format!("{ANIMALS}
+        var p = new Puppy(\"Bit\", \"pug\");
+        if p instanceof Animal {{ echo \"animal\"; }}
+        if p instanceof Dog {{ echo \"dog\"; }}
+        if new Animal(\"x\") instanceof Dog {{ echo \"wrong\"; }}
+        if 5 instanceof Dog {{ echo \"wrong\"; }}
+    ");
+    assert_eq!(output(&script), "animal\ndog\n");
+}
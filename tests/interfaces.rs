@@ -0,0 +1,63 @@
+mod common;
+
+use common::{output, run};
+
+#[test]
+fn traits_and_interfaces_combine_in_a_class() {
+    let script = "
+        interface Named {
+            fn name();
+        }
+        interface Shape extends Named {
+            fn area();
+        }
+        trait Describes {
+            var unit = \"cm\";
+            fn describe() {
+                return this->name();
+            }
+            abstract fn name();
+        }
+        abstract class Base implements Shape {
+            use Describes;
+            abstract fn sides();
+        }
+        class Square extends Base {
+            var side = 2;
+            fn area() { return this->side * this->side; }
+            fn sides() { return 4; }
+            fn name() { return \"square\"; }
+        }
+        var s = new Square();
+        echo s->area();
+        echo s->describe();
+        echo s->unit;
+        if s instanceof Named { echo \"named\"; }
+    ";
+    assert_eq!(output(script), "4\nsquare\ncm\nnamed\n");
+}
+
+#[test]
+fn classes_must_implement_interface_methods() {
+    let script = "
+        interface I { fn f(); fn g(a); }
+        class C implements I {
+            fn f() {}
+        }
+    ";
+    assert!(run(script).stderr.contains("class C must implement I::g() or be declared abstract"));
+}
+
+#[test]
+fn abstract_classes_cannot_be_instantiated() {
+    let script = "
+        abstract class A { abstract fn f(); }
+        var a = new A();
+    ";
+    assert!(run(script).stderr.contains("cannot instantiate abstract class A"));
+    let script = "
+        abstract class A { abstract fn f(); }
+        class B extends A {}
+    ";
+    assert!(run(script).stderr.contains("class B must implement A::f() or be declared abstract"));
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use common::run_with;
+
+/// The JSON lines printed for `source`, with the script's temporary path taken out.
+fn json_lines(source: &str) -> Vec<String> {
+    let run = run_with(source, &["--error-format=json"]);
+    run.stderr
+        .lines()
+        .map(|line| {
+            let (before, rest) = line.split_once("\"file\":\"").unwrap();
+            let (_, after) = rest.split_once("\",").unwrap();
+            format!("{before}\"file\":\"script\",{after}")
+        })
+        .collect()
+}
+
+#[test]
+fn each_error_is_one_json_line() {
+    assert_eq!(json_lines("echo 1 +;\necho @;"), [
+        r#"{"severity":"error","code":"E0003","kind":"InvalidSyntax","message":"expected expression, found ';'","help":null,"file":"script","line":1,"column":9,"span":{"start":8,"end":9}}"#,
+        r#"{"severity":"error","code":"E0001","kind":"ScannerError","message":"unexpected character: '@'","help":null,"file":"script","line":2,"column":6,"span":{"start":15,"end":16}}"#,
+        r#"{"severity":"error","code":"E0003","kind":"InvalidSyntax","message":"expected expression, found ';'","help":null,"file":"script","line":2,"column":7,"span":{"start":16,"end":17}}"#,
+    ]);
+}
+
+#[test]
+fn help_and_quotes_are_encoded() {
+    assert_eq!(json_lines("var total = 1;\necho totl;\n"), [
+        r#"{"severity":"error","code":"E0004","kind":"RuntimeError","message":"undefined variable 'totl'","help":"did you mean `total`?","file":"script","line":2,"column":6,"span":{"start":20,"end":24}}"#,
+    ]);
+    assert_eq!(
+        json_lines("echo \"abc;")[0],
+        r#"{"severity":"error","code":"E0001","kind":"ScannerError","message":"missing \"","help":null,"file":"script","line":1,"column":6,"span":{"start":5,"end":6}}"#
+    );
+}
+
+#[test]
+fn the_exit_code_is_unchanged() {
+    assert_eq!(run_with("echo nope;", &["--error-format=json"]).code, 70);
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::{output, run};
+
+#[test]
+fn maps_keep_insertion_order() {
+    let script = "
+        var person = [\"name\" => \"Ann\", \"age\" => 30, 5 => \"five\"];
+        echo person[\"name\"];
+        person[\"city\"] = \"Oslo\";
+        person[] = \"next\";
+        echo person;
+    ";
+    assert_eq!(output(script), "Ann\n[name => Ann, age => 30, 5 => five, city => Oslo, 6 => next]\n");
+}
+
+#[test]
+fn keys_are_normalized_like_php() {
+    let script = "
+        var k = [\"1\" => \"a\", \"01\" => \"b\", true => \"d\", Null => \"e\", \"-3\" => \"f\"];
+        echo k;
+        echo k[\"1\"];
+        echo count(k);
+    ";
+    assert_eq!(output(script), "[1 => d, 01 => b,  => e, -3 => f]\nd\n4\n");
+}
+
+#[test]
+fn unset_removes_keys_without_renumbering() {
+    let script = "
+        var list = [10, 20, 30];
+        unset(list[1]);
+        echo list;
+        list[] = 40;
+        echo list;
+        var nested = [\"a\" => [\"b\" => 1]];
+        nested[\"a\"][\"c\"] = 2;
+        unset(nested[\"a\"][\"b\"]);
+        echo nested;
+    ";
+    assert_eq!(output(script), "[0 => 10, 2 => 30]\n[0 => 10, 2 => 30, 3 => 40]\n[a => [c => 2]]\n");
+}
+
+#[test]
+fn reading_a_missing_key_is_an_error() {
+    let run = run("var m = [\"a\" => 1]; echo m[\"missing\"];");
+    assert!(run.stderr.contains("undefined array key \"missing\""));
+}
+
+#[test]
+fn appending_after_the_largest_int_key_is_an_error() {
+    let script = "
+        var a = [9223372036854775807 => 1];
+        echo a;
+        a[] = 2;
+    ";
+    let run = run(script);
+    assert_eq!(run.code, 70);
+    assert_eq!(run.stdout, "[9223372036854775807 => 1]\n");
+    assert!(run.stderr.contains("cannot add element to the array as the next element is already occupied"));
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use common::{output, run};
+
+#[test]
+fn match_picks_the_first_matching_arm() {
+    let script = "
+        fn size(x) {
+            return match(x) {
+                1, 2 => \"low\",
+                3 => \"mid\",
+                default => \"high\",
+            };
+        }
+        echo size(1);
+        echo size(2);
+        echo size(3);
+        echo size(10);
+        echo match(\"a\") { \"a\" => 1 } + 1;
+    ";
+    assert_eq!(output(script), "low\nlow\nmid\nhigh\n2\n");
+}
+
+#[test]
+fn match_compares_strictly() {
+    let script = "
+        echo match(\"1\") { 1 => \"int\", default => \"string\" };
+        echo match (true) { 1 => \"one\", true => \"true\" };
+    ";
+    assert_eq!(output(script), "string\ntrue\n");
+}
+
+#[test]
+fn an_unmatched_value_is_an_error() {
+    let run = run("echo match(5) { 1 => \"one\" };");
+    assert!(run.stderr.contains("Uncaught UnhandledMatchError: Unhandled match case 5"));
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn division_gives_an_int_only_when_exact() {
+    let script = "
+        echo 10/2;
+        echo 7/2;
+        echo 10/3;
+        echo 10/3*3;
+        echo 1/3;
+    ";
+    assert_eq!(output(script), "5\n3.5\n3.3333333333333\n10\n0.33333333333333\n");
+}
+
+#[test]
+fn integer_literals_in_every_base() {
+    let script = "
+        echo 1_000_000;
+        echo 0x1F;
+        echo 0XfF;
+        echo 0o17;
+        echo 017;
+        echo 0b1010;
+        echo 1e3;
+        echo 2E+2;
+    ";
+    assert_eq!(output(script), "1000000\n31\n255\n15\n15\n10\n1000\n200\n");
+}
+
+#[test]
+fn integer_overflow_becomes_a_float() {
+    let script = "
+        echo 9223372036854775807;
+        echo 9223372036854775807 + 1;
+        echo 9223372036854775808;
+        echo -9223372036854775807 - 2;
+        echo 3037000500 * 3037000500;
+        echo 2 ** 62;
+        echo 2 ** 63;
+        var i = 9223372036854775807; i++; echo i;
+        echo -(-9223372036854775807 - 1);
+    ";
+    let expected = "9223372036854775807\n9.2233720368548E+18\n9.2233720368548E+18\n-9.2233720368548E+18\n\
+                    9.2233720370002E+18\n4611686018427387904\n9.2233720368548E+18\n9.2233720368548E+18\n\
+                    9.2233720368548E+18\n";
+    assert_eq!(output(script), expected);
+}
+
+#[test]
+fn floats_print_like_php() {
+    let script = "
+        echo 0.1 + 0.2;
+        echo 2.75 + 0.25;
+        echo 3.0;
+        echo 1e100;
+        echo 0.00001;
+        echo 0.0001;
+        echo 1e14;
+        echo 1e13;
+        echo 12345678901234567.0;
+        echo [1, 2.5, 3];
+    ";
+    let expected = "0.3\n3\n3\n1.0E+100\n1.0E-5\n0.0001\n1.0E+14\n10000000000000\n1.2345678901235E+16\n[1, 2.5, 3]\n";
+    assert_eq!(output(script), expected);
+}
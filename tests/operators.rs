@@ -0,0 +1,83 @@
+mod common;
+
+use common::{output, run};
+
+#[test]
+fn modulo_and_powers() {
+    let script = "
+        echo 7 % 3;
+        echo -7 % 3;
+        echo 2 ** 10;
+        echo -2 ** 2;
+        echo 2 ** 3 ** 2;
+        echo 2 ** -1;
+        echo 2 + 3 * 4 % 5;
+    ";
+    assert_eq!(output(script), "1\n-1\n1024\n-4\n512\n0.5\n4\n");
+}
+
+#[test]
+fn bitwise_and_shift_operators() {
+    let script = "
+        echo 6 & 3;
+        echo 6 | 3;
+        echo 6 ^ 3;
+        echo ~5;
+        echo 1 << 4;
+        echo -16 >> 2;
+        echo 1 << 64;
+        echo 1 + 2 << 1;
+        var x = 12;
+        x &= 10; echo x;
+        x |= 1; echo x;
+        x ^= 3; echo x;
+        x <<= 2; echo x;
+        x >>= 1; echo x;
+    ";
+    assert_eq!(output(script), "2\n7\n5\n-6\n16\n-4\n0\n6\n8\n9\n10\n40\n20\n");
+}
+
+#[test]
+fn intdiv_and_fmod_truncate_towards_zero() {
+    let script = "
+        echo intdiv(7, 2);
+        echo intdiv(-7, 2);
+        echo fmod(-7, 3);
+    ";
+    assert_eq!(output(script), "3\n-3\n-1\n");
+}
+
+#[test]
+fn arithmetic_errors_can_be_caught() {
+    let script = "
+        try { echo 1 / 0; } catch (DivisionByZeroError e) { echo e->getMessage(); }
+        try { echo 1 % 0; } catch (ArithmeticError e) { echo e->getMessage(); }
+        try { echo intdiv(1, 0); } catch (DivisionByZeroError e) { echo e->getMessage(); }
+        try { echo 1 << -1; } catch (ArithmeticError e) { echo e->getMessage(); }
+        try { echo intdiv(\"a\", 1); } catch (TypeError e) { echo e->getMessage(); }
+    ";
+    let expected = "Division by zero\nModulo by zero\nDivision by zero\nBit shift by negative number\n\
+                    intdiv(): argument #1 must be a number, string given\n";
+    assert_eq!(output(script), expected);
+}
+
+#[test]
+fn uncaught_division_by_zero_stops_the_script() {
+    let run = run("echo 1 / 0;");
+    assert_eq!(run.code, 255);
+    assert!(run.stderr.contains("Uncaught DivisionByZeroError: Division by zero"));
+}
+
+#[test]
+fn unary_plus_converts_to_a_number() {
+    let script = "
+        fn f() { return 3; }
+        echo +5;
+        echo f() + + 2;
+        echo +\"1.5\";
+        echo +true;
+        echo 1 - +2;
+        try { echo +[1]; } catch (TypeError e) { echo e->getMessage(); }
+    ";
+    assert_eq!(output(script), "5\n5\n1.5\n1\n-1\nUnsupported operand types: array * int\n");
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn every_syntax_error_is_reported() {
+    let script = "
+        var a = 1 +;
+        echo \"ok\";
+        fn f(1) {}
+        var b = 2
+        echo b;
+    ";
+    let run = run(script);
+    assert_eq!(run.code, 65);
+    assert_eq!(run.stdout, "");
+    assert!(run.stderr.contains("expected expression, found ';'"));
+    assert!(run.stderr.contains("expected parameter name"));
+    assert!(run.stderr.contains("Missing semicolon"));
+}
+
+#[test]
+fn scanner_and_parser_errors_are_reported_together() {
+    let run = run("echo #;\nvar = 1;");
+    assert_eq!(run.code, 65);
+    assert!(run.stderr.contains("unexpected character: '#'"));
+    assert!(run.stderr.contains("expected variable name"));
+}
+
+#[test]
+fn errors_inside_switch_and_match_are_reported_once_each() {
+    let script = "
+        switch (1) {
+            case 1:
+                echo 1 +;
+                echo \"fine\";
+                break;
+            case 2 +:
+                echo \"two\";
+            default:
+                echo 3 *;
+        }
+        var y = match (1) {
+            1 => 2 +,
+            2 => \"ok\",
+            default => 4 *
+        };
+        echo 5 -;
+    ";
+    let run = run(script);
+    assert_eq!(run.code, 65);
+    assert_eq!(run.stderr.matches("error[").count(), 6);
+    assert_eq!(run.stderr.matches("expected expression").count(), 6);
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn var_in_a_block_shadows_without_leaking() {
+    let script = "
+        var x = \"global\";
+        var i = 0;
+        while i < 2 {
+            var x = \"loop\";
+            echo x;
+            i = i + 1;
+        }
+        echo x;
+    ";
+    assert_eq!(output(script), "loop\nloop\nglobal\n");
+}
+
+#[test]
+fn assignment_updates_the_nearest_binding() {
+    let script = "
+        var x = \"global\";
+        {
+            var y = 1;
+            x = \"changed\";
+        }
+        echo x;
+        var total = 0;
+        fn add_to(n) {
+            total = total + n;
+        }
+        add_to(3);
+        add_to(4);
+        echo total;
+    ";
+    assert_eq!(output(script), "changed\n7\n");
+}
+
+#[test]
+fn function_locals_stay_in_the_call() {
+    let script = "
+        var x = \"global\";
+        fn f() {
+            var x = \"local\";
+            echo x;
+        }
+        f();
+        echo x;
+    ";
+    assert_eq!(output(script), "local\nglobal\n");
+}
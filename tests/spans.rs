@@ -0,0 +1,22 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn runtime_errors_point_at_line_and_column() {
+    let run = run("var a = 1;\n  echo  nope;\n");
+    assert!(run.stderr.contains(":2:9"), "{}", run.stderr);
+}
+
+#[test]
+fn columns_count_characters_not_bytes() {
+    let run = run("var s = \"é\"; echo nope;\n");
+    assert!(run.stderr.contains(":1:19"), "{}", run.stderr);
+}
+
+#[test]
+fn lines_continue_after_multi_line_strings() {
+    let run = run("var s = \"a\nb\" + @;\n");
+    assert!(run.stderr.contains(":2:6"), "{}", run.stderr);
+    assert!(run.stderr.contains("unexpected character: '@'"));
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::output;
+
+const DESCRIBE: &str = "
+    fn describe(x) {
+        switch (x) {
+            case 1:
+                echo \"one\";
+            case 2:
+                echo \"two\";
+                break;
+            case \"3\":
+                echo \"three\";
+                break;
+            default:
+                echo \"other\";
+            case 9:
+                echo \"nine\";
+        }
+    }
+";
+
+#[test]
+fn cases_fall_through_until_break() {
+    let script = format!("{DESCRIBE}
+        describe(1);
+        describe(9);
+    ");
+    assert_eq!(output(&script), "one\ntwo\nnine\n");
+}
+
+#[test]
+fn cases_match_loosely_and_default_falls_through() {
+    let script = format!("{DESCRIBE}
+        describe(\"2\");
+        describe(3);
+        describe(7);
+    ");
+    assert_eq!(output(&script), "two\nthree\nother\nnine\n");
+}
+
+#[test]
+fn continue_and_return_leave_a_switch() {
+    let script = "
+        for (var i = 0; i < 3; i = i + 1) {
+            switch (i) {
+                case 1:
+                    continue 2;
+                default:
+                    echo i;
+            }
+        }
+        fn inside() {
+            switch (5) {
+                case 5:
+                    return \"returned\";
+            }
+        }
+        echo inside();
+    ";
+    assert_eq!(output(script), "0\n2\nreturned\n");
+}
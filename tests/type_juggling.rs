@@ -0,0 +1,126 @@
+mod common;
+
+use common::output;
+
+#[test]
+fn arithmetic_converts_operands_to_numbers() {
+    let script = "
+        echo \"5\" + 3;
+        echo \"1.5\" * 2;
+        echo \" 7 apples\" + 1;
+        echo true + 1;
+        echo Null + 4;
+        echo -\"3\";
+        var x = \"5\";
+        x += 2;
+        echo x;
+    ";
+    assert_eq!(output(script), "8\n3\n8\n2\n4\n-3\n7\n");
+}
+
+#[test]
+fn concatenation_converts_operands_to_strings() {
+    let script = "
+        echo \"a\" . 1 . 2.5 . true . Null . false;
+        echo \"x\" . 1 + 2;
+        var s = \"hi\";
+        s .= 5;
+        echo s;
+    ";
+    assert_eq!(output(script), "a12.51\nx3\nhi5\n");
+}
+
+#[test]
+fn loose_and_strict_comparison() {
+    let script = "
+        echo 1 == true;
+        echo \"abc\" == 0;
+        echo \"1\" == \"01\";
+        echo \"10\" == \"1e1\";
+        echo 100 == \"1e2\";
+        echo Null == false;
+        echo 1 === \"1\";
+        echo 1 !== 1.0;
+        echo [1, 2] == [1, 2];
+        echo [1, 2] === [2 => 1];
+    ";
+    assert_eq!(output(script), "1\n\n1\n1\n1\n1\n\n1\n1\n\n");
+}
+
+#[test]
+fn spaceship_orders_mixed_values() {
+    let script = "
+        echo 1 <=> 2;
+        echo \"b\" <=> \"a\";
+        echo [1, 2, 3] <=> [1, 2, 4];
+        echo Null <=> \"a\";
+        echo 5 <=> \"abc\";
+    ";
+    assert_eq!(output(script), "-1\n1\n-1\n-1\n-1\n");
+}
+
+#[test]
+fn conditions_use_php_truthiness() {
+    let script = "
+        if \"0\" { echo \"no\"; } else { echo \"falsy zero string\"; }
+        if [1] { echo \"non-empty array\"; }
+        var n = 2;
+        while n { echo n; n = n - 1; }
+        if 0 && undefined_thing { echo \"no\"; }
+        if 1 || undefined_thing { echo \"short circuit\"; }
+    ";
+    assert_eq!(output(script), "falsy zero string\nnon-empty array\n2\n1\nshort circuit\n");
+}
+
+#[test]
+fn non_numeric_operands_throw_type_errors() {
+    let script = "
+        try { echo \"abc\" * 2; } catch (TypeError e) { echo e->getMessage(); }
+        try { echo [1] - 1; } catch (TypeError e) { echo e->getMessage(); }
+        try { echo ~\"x\"; } catch (TypeError e) { echo e->getMessage(); }
+    ";
+    let expected = "Unsupported operand types: string * int\nUnsupported operand types: array - int\n\
+                    Cannot perform bitwise not on string\n";
+    assert_eq!(output(script), expected);
+}
+
+#[test]
+fn objects_that_refer_to_themselves_compare_without_recursing_forever() {
+    let script = "
+        class N { var next = Null; var v = 1; }
+        var a = new N();
+        a->next = a;
+        var b = new N();
+        b->next = b;
+        echo a == a;
+        echo a <=> b;
+        b->v = 2;
+        echo a == b;
+        echo a < b;
+    ";
+    assert_eq!(output(script), "1\n0\n\n1\n");
+}
+
+#[test]
+fn ints_compare_with_integer_strings_exactly() {
+    let script = "
+        echo 9223372036854775807 == \"9223372036854775806\";
+        echo 9223372036854775807 == \"9223372036854775807\";
+        echo 9223372036854775807 <=> \"9223372036854775806\";
+        echo 100 == \"1e2\";
+        echo 10 == \"10abc\";
+    ";
+    assert_eq!(output(script), "\n1\n1\n1\n\n");
+}
+
+#[test]
+fn echo_converts_scalars_like_php() {
+    let script = "
+        echo true;
+        echo false;
+        echo Null;
+        echo 1.5;
+        echo [1, 2];
+    ";
+    assert_eq!(output(script), "1\n\n\n1.5\n[1, 2]\n");
+}